@@ -0,0 +1,36 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+/// Identifies a node of the embedder's accessibility tree. Must stay the
+/// same for the lifetime of the node.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct AccessibilityNodeId(pub u64);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccessibilityRole {
+    Group,
+    StaticText,
+    Heading,
+    Link,
+    Button,
+    CheckBox,
+    TextField,
+    Image,
+    List,
+}
+
+/// Answers the screen reader queries about the content of a View, see
+/// `View::set_accessibility_provider`. `None` stands for the view itself.
+pub trait AccessibilityProvider {
+    fn children(&self, node: Option<AccessibilityNodeId>) -> Vec<AccessibilityNodeId>;
+    fn parent(&self, node: AccessibilityNodeId) -> Option<AccessibilityNodeId>;
+    fn role(&self, node: AccessibilityNodeId) -> AccessibilityRole;
+    fn label(&self, node: AccessibilityNodeId) -> Option<String>;
+    /// Content of text fields, state of check boxes…
+    fn value(&self, _node: AccessibilityNodeId) -> Option<String> {
+        None
+    }
+    /// Node with the keyboard focus, if any.
+    fn focused(&self) -> Option<AccessibilityNodeId>;
+}
@@ -3,6 +3,56 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 pub use platform::App;
+use window::WindowId;
+use screen::ScreenId;
+use priority::{Priority, Prioritized};
+use clock::{Clock, SystemClock};
+use std::fmt;
+use std::rc::Rc;
+use std::time::Duration;
+
+/// Run loop settings, see `App::load_with_options`.
+#[derive(Clone)]
+pub struct AppOptions {
+    /// Wake up the run loop at this interval, even without any event.
+    /// `None` (the default) only wakes up on events.
+    pub tick_interval: Option<Duration>,
+    /// Stop ticking once nothing happened for that long. The next event
+    /// (input, Servo rising the event loop, or View::request_redraw)
+    /// resumes the ticks.
+    pub idle_after: Option<Duration>,
+    /// Timestamps events for idle detection, latency measurements, redraw
+    /// throttling and click counting. Shared by all the windows and views.
+    /// SystemClock by default. Use a MockClock to make tests deterministic.
+    pub clock: Rc<Clock>,
+    /// NSEventMask bits of the events the run loop dequeues. `None` (the
+    /// default) is NSAnyEventMask. Application-defined events are always
+    /// dequeued, as they wake up the loop. Excluding mouse, key or
+    /// AppKit/system-defined events breaks input and window activation.
+    /// Excluding periodic events disables autoscroll.
+    pub event_mask: Option<u64>,
+}
+
+impl Default for AppOptions {
+    fn default() -> AppOptions {
+        AppOptions {
+            tick_interval: None,
+            idle_after: None,
+            clock: Rc::new(SystemClock),
+            event_mask: None,
+        }
+    }
+}
+
+impl fmt::Debug for AppOptions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AppOptions")
+         .field("tick_interval", &self.tick_interval)
+         .field("idle_after", &self.idle_after)
+         .field("event_mask", &self.event_mask)
+         .finish()
+    }
+}
 
 #[derive(Clone, Debug)]
 pub enum AppEvent {
@@ -10,10 +60,139 @@ pub enum AppEvent {
     WillTerminate,
     DidChangeScreenParameters,
     DoCommand(AppCommand),
+    ProxyIconDragged(WindowId),
+    WindowAppearanceChanged(WindowId, Appearance),
+    AccessibilityDisplayOptionsChanged,
+    WindowDidBecomeKey(WindowId),
+    MemoryPressure(PressureLevel),
+    /// Picked from the Open Recent menu, see `App::note_recent_url`.
+    OpenRecent(String),
+    /// The window moved to a display with a different refresh rate (Hz).
+    RefreshRateChanged(WindowId, f32),
+    /// A menu item handled by the embedder. "about", if the About menu item
+    /// isn't showing the standard panel (see `App::set_about_options`).
+    MenuAction(String),
+    /// Ctrl+Tab / Ctrl+Shift+Tab selected this tab of a native tab group.
+    TabSelected(WindowId),
+    /// A shortcut the embedder is expected to handle. For example,
+    /// Ctrl+Tab in a window that is not part of a native tab group.
+    Shortcut(Shortcut),
+    /// The app got hidden (Cmd+H, or Hide Others from another app). Its
+    /// windows aren't visible, rendering can be paused.
+    DidHide,
+    DidUnhide,
+    /// A display got plugged in or unplugged. Broader than
+    /// DidChangeScreenParameters, which doesn't tell which display.
+    DisplayConnected(ScreenId),
+    DisplayDisconnected(ScreenId),
+    /// The user changed the scrolling preferences, see `App::scroll_settings`.
+    ScrollSettingsChanged(ScrollSettings),
+}
+
+impl Prioritized for AppEvent {
+    fn priority(&self) -> Priority {
+        match *self {
+            AppEvent::WillTerminate |
+            AppEvent::DoCommand(..) |
+            AppEvent::OpenRecent(..) |
+            AppEvent::MenuAction(..) |
+            AppEvent::TabSelected(..) |
+            AppEvent::Shortcut(..) => Priority::High,
+            AppEvent::DidFinishLaunching |
+            AppEvent::DidChangeScreenParameters |
+            AppEvent::ProxyIconDragged(..) |
+            AppEvent::WindowAppearanceChanged(..) |
+            AppEvent::AccessibilityDisplayOptionsChanged |
+            AppEvent::WindowDidBecomeKey(..) |
+            AppEvent::MemoryPressure(..) |
+            AppEvent::RefreshRateChanged(..) |
+            AppEvent::DidHide |
+            AppEvent::DidUnhide |
+            AppEvent::DisplayConnected(..) |
+            AppEvent::DisplayDisconnected(..) |
+            AppEvent::ScrollSettingsChanged(..) => Priority::Medium,
+        }
+    }
+}
+
+/// System memory pressure. On Warning and Critical, caches should be released.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PressureLevel {
+    Normal,
+    Warning,
+    Critical,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Appearance {
+    Light,
+    Dark,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shortcut {
+    NextTab,
+    PreviousTab,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum AppCommand {
     ClearHistory,
+    ClearRecent,
     ToggleOptionDarkTheme,
 }
+
+/// What the standard About panel shows. Fields set to `None` fall back
+/// to the Info.plist values.
+#[derive(Clone, Debug, Default)]
+pub struct AboutOptions {
+    pub application_name: Option<String>,
+    /// Short version, like "1.2"
+    pub application_version: Option<String>,
+    /// Build version, shown in parentheses after the short version
+    pub version: Option<String>,
+    pub copyright: Option<String>,
+    pub credits: Option<String>,
+}
+
+/// One input-to-present measurement, as reported to the hook
+/// installed with `App::set_latency_hook`.
+#[derive(Clone, Debug)]
+pub struct LatencySample {
+    pub kind: LatencyEventKind,
+    pub ms: f64,
+}
+
+/// Scrolling preferences of the user, see `App::scroll_settings`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScrollSettings {
+    /// Mouse wheel scrolling speed, from 0.0 (slowest) to 5.0 (fastest).
+    /// com.apple.scrollwheel.scaling, 0.3125 by default.
+    pub speed: f32,
+    /// False if acceleration has been turned off, by setting
+    /// com.apple.scrollwheel.scaling to -1.
+    pub acceleration: bool,
+    /// "Natural" scroll direction (content follows the fingers).
+    /// com.apple.swipescrolldirection, true by default.
+    pub natural: bool,
+}
+
+/// Where the time went between two presented frames, as reported to the
+/// hook installed with `App::set_frame_stats_hook`. In milliseconds.
+#[derive(Clone, Copy, Debug)]
+pub struct FrameStats {
+    /// Blocked in the run loop, waiting for events.
+    pub waiting_ms: f64,
+    /// In the callback passed to `App::run` (event handling, Servo).
+    pub callback_ms: f64,
+    /// Since the previous present. None for the first frame.
+    pub since_last_present_ms: Option<f64>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LatencyEventKind {
+    MouseInput,
+    MouseMoved,
+    MouseWheel,
+    Key,
+}
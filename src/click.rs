@@ -0,0 +1,121 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use clock::duration_to_secs;
+use std::time::Duration;
+use view::MouseButton;
+
+/// Double-click interval used when the platform doesn't provide one.
+pub const DEFAULT_DOUBLE_CLICK_INTERVAL_MS: u64 = 500;
+
+/// Clicks further apart than that (in device pixels, on any axis) start a
+/// new sequence.
+pub const DEFAULT_DOUBLE_CLICK_DISTANCE: i32 = 4;
+
+/// Turns button presses into click counts (1: single click, 2: double
+/// click…), for backends without native click counting.
+pub struct ClickCounter {
+    // In seconds
+    interval: f64,
+    distance: i32,
+    // Time, location, button and count of the last press
+    last: Option<(f64, (i32, i32), MouseButton, u32)>,
+}
+
+impl ClickCounter {
+    pub fn new() -> ClickCounter {
+        ClickCounter {
+            interval: DEFAULT_DOUBLE_CLICK_INTERVAL_MS as f64 / 1000.0,
+            distance: DEFAULT_DOUBLE_CLICK_DISTANCE,
+            last: None,
+        }
+    }
+
+    pub fn set_interval(&mut self, interval: Duration) {
+        self.interval = duration_to_secs(interval);
+    }
+
+    /// Record a press of `button` at `point`, at `now` (see `Clock::now`).
+    /// Returns its click count.
+    pub fn press(&mut self, now: f64, point: (i32, i32), button: MouseButton) -> u32 {
+        let count = match self.last {
+            Some((time, (x, y), last_button, count))
+                if last_button == button &&
+                   now - time <= self.interval &&
+                   (point.0 - x).abs() <= self.distance &&
+                   (point.1 - y).abs() <= self.distance => count + 1,
+            _ => 1,
+        };
+        self.last = Some((now, point, button, count));
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ClickCounter, DEFAULT_DOUBLE_CLICK_DISTANCE};
+    use clock::{Clock, MockClock};
+    use std::time::Duration;
+    use view::MouseButton;
+
+    fn counter() -> ClickCounter {
+        let mut counter = ClickCounter::new();
+        counter.set_interval(Duration::from_millis(500));
+        counter
+    }
+
+    #[test]
+    fn clicks_within_the_interval_increment() {
+        let clock = MockClock::new();
+        let mut counter = counter();
+        assert_eq!(counter.press(clock.now(), (10, 10), MouseButton::Left), 1);
+        clock.advance(Duration::from_millis(100));
+        assert_eq!(counter.press(clock.now(), (10, 10), MouseButton::Left), 2);
+        clock.advance(Duration::from_millis(100));
+        assert_eq!(counter.press(clock.now(), (10, 10), MouseButton::Left), 3);
+    }
+
+    #[test]
+    fn clicks_outside_the_interval_reset() {
+        let clock = MockClock::new();
+        let mut counter = counter();
+        assert_eq!(counter.press(clock.now(), (10, 10), MouseButton::Left), 1);
+        clock.advance(Duration::from_millis(100));
+        assert_eq!(counter.press(clock.now(), (10, 10), MouseButton::Left), 2);
+        clock.advance(Duration::from_millis(600));
+        assert_eq!(counter.press(clock.now(), (10, 10), MouseButton::Left), 1);
+    }
+
+    #[test]
+    fn click_exactly_at_the_interval_increments() {
+        let clock = MockClock::new();
+        let mut counter = counter();
+        assert_eq!(counter.press(clock.now(), (10, 10), MouseButton::Left), 1);
+        clock.advance(Duration::from_millis(500));
+        assert_eq!(counter.press(clock.now(), (10, 10), MouseButton::Left), 2);
+        clock.advance(Duration::from_millis(501));
+        assert_eq!(counter.press(clock.now(), (10, 10), MouseButton::Left), 1);
+    }
+
+    #[test]
+    fn clicks_beyond_the_distance_reset() {
+        let clock = MockClock::new();
+        let mut counter = counter();
+        let d = DEFAULT_DOUBLE_CLICK_DISTANCE;
+        assert_eq!(counter.press(clock.now(), (10, 10), MouseButton::Left), 1);
+        assert_eq!(counter.press(clock.now(), (10 + d, 10 - d), MouseButton::Left), 2);
+        assert_eq!(counter.press(clock.now(), (10 + 2 * d + 1, 10 - d), MouseButton::Left), 1);
+        assert_eq!(counter.press(clock.now(), (10 + 2 * d + 1, 10 + 1), MouseButton::Left), 1);
+    }
+
+    #[test]
+    fn clicks_with_another_button_reset() {
+        let clock = MockClock::new();
+        let mut counter = counter();
+        assert_eq!(counter.press(clock.now(), (10, 10), MouseButton::Left), 1);
+        assert_eq!(counter.press(clock.now(), (10, 10), MouseButton::Left), 2);
+        assert_eq!(counter.press(clock.now(), (10, 10), MouseButton::Right), 1);
+        assert_eq!(counter.press(clock.now(), (10, 10), MouseButton::Left), 1);
+    }
+}
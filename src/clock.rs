@@ -0,0 +1,61 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+pub use platform::SystemClock;
+use std::cell::Cell;
+use std::time::Duration;
+
+/// Source of the timestamps used by the event pipeline (idle detection,
+/// latency measurements…). See `AppOptions::clock`.
+pub trait Clock {
+    /// Monotonic time, in seconds. The origin is up to the implementation.
+    fn now(&self) -> f64;
+}
+
+/// In seconds, like `Clock::now`.
+pub fn duration_to_secs(duration: Duration) -> f64 {
+    duration.as_secs() as f64 + duration.subsec_nanos() as f64 / 1_000_000_000.0
+}
+
+/// A clock that only moves when told to. For tests.
+pub struct MockClock {
+    now: Cell<f64>,
+}
+
+impl MockClock {
+    pub fn new() -> MockClock {
+        MockClock {
+            now: Cell::new(0.0),
+        }
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        self.now.set(self.now.get() + duration_to_secs(duration));
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> f64 {
+        self.now.get()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Clock, MockClock};
+    use std::time::Duration;
+
+    #[test]
+    fn mock_clock_only_moves_when_advanced() {
+        let clock = MockClock::new();
+        assert_eq!(clock.now(), 0.0);
+        assert_eq!(clock.now(), 0.0);
+        clock.advance(Duration::from_millis(1500));
+        assert_eq!(clock.now(), 1.5);
+        clock.advance(Duration::new(2, 250_000_000));
+        assert_eq!(clock.now(), 3.75);
+        clock.advance(Duration::from_millis(0));
+        assert_eq!(clock.now(), 3.75);
+    }
+}
@@ -0,0 +1,85 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+pub use platform::Controls;
+
+#[derive(Clone, Debug)]
+pub enum ControlEvent {
+    /// The user answered a prompt from `Controls::show_permission_prompt`.
+    /// Dismissing the prompt counts as a denial.
+    PermissionResponse(PermissionKind, bool),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PermissionKind {
+    Camera,
+    Microphone,
+    Geolocation,
+    Notifications,
+}
+
+/// Shown as a lock in the urlbar.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SecurityState {
+    /// Valid certificate, no mixed content.
+    Secure,
+    /// Plain HTTP.
+    Insecure,
+    /// Certificate error or mixed content.
+    Broken,
+}
+
+/// Changes to apply at once with `Controls::update`. Unset fields are left
+/// as they are.
+#[derive(Clone, Debug, Default)]
+pub struct ControlsUpdate {
+    pub url: Option<String>,
+    pub error_state: Option<Option<String>>,
+    pub loading: Option<bool>,
+    /// Encoded image (PNG, ICO…)
+    pub favicon: Option<Option<Vec<u8>>>,
+    pub security_state: Option<Option<SecurityState>>,
+    /// From 0.0 to 1.0
+    pub progress: Option<Option<f32>>,
+}
+
+impl ControlsUpdate {
+    /// Text of the urlbar.
+    pub fn set_url(&mut self, url: &str) -> &mut ControlsUpdate {
+        self.url = Some(url.to_owned());
+        self
+    }
+
+    /// See `Controls::set_error_state`.
+    pub fn set_error_state(&mut self, error: Option<&str>) -> &mut ControlsUpdate {
+        self.error_state = Some(error.map(|error| error.to_owned()));
+        self
+    }
+
+    /// Spin the urlbar throbber.
+    pub fn set_loading(&mut self, loading: bool) -> &mut ControlsUpdate {
+        self.loading = Some(loading);
+        self
+    }
+
+    /// Icon at the start of the urlbar, from encoded image data (anything
+    /// NSImage can read on Mac). `None` removes it.
+    pub fn set_favicon(&mut self, favicon: Option<&[u8]>) -> &mut ControlsUpdate {
+        self.favicon = Some(favicon.map(|data| data.to_vec()));
+        self
+    }
+
+    /// Lock at the start of the urlbar. `None` removes it.
+    pub fn set_security_state(&mut self, state: Option<SecurityState>) -> &mut ControlsUpdate {
+        self.security_state = Some(state);
+        self
+    }
+
+    /// Fill the urlbar throbber (0.0 to 1.0, clamped) instead of spinning
+    /// it. `None` goes back to spinning, see `set_loading`.
+    pub fn set_progress(&mut self, progress: Option<f32>) -> &mut ControlsUpdate {
+        self.progress = Some(progress.map(|p| p.max(0.0).min(1.0)));
+        self
+    }
+}
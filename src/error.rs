@@ -0,0 +1,27 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use std::fmt;
+
+#[derive(Debug)]
+pub enum ShellError {
+    // A nib file can't be loaded, or doesn't contain what we expect
+    Nib(&'static str),
+    // No NSServoView in the window. Comes with the view hierarchy of the window.
+    ServoViewNotFound(String),
+    // Not a valid Uniform Type Identifier
+    InvalidPasteboardType(String),
+}
+
+impl fmt::Display for ShellError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ShellError::Nib(msg) => write!(f, "Nib error: {}", msg),
+            ShellError::ServoViewNotFound(ref hierarchy) => {
+                write!(f, "Can't find NSServoView (identifier shellViewServo) in:\n{}", hierarchy)
+            }
+            ShellError::InvalidPasteboardType(ref uti) => write!(f, "Invalid pasteboard type: {:?}", uti),
+        }
+    }
+}
@@ -10,6 +10,9 @@ extern crate objc;
 #[macro_use]
 extern crate log;
 
+#[macro_use]
+extern crate bitflags;
+
 extern crate libc;
 extern crate cocoa;
 extern crate objc_foundation;
@@ -21,10 +24,20 @@ mod view;
 mod servo;
 mod platform;
 mod state;
-
-use app::{App, AppEvent, AppCommand};
+mod error;
+mod clipboard;
+mod controls;
+mod priority;
+mod clock;
+mod screen;
+mod accessibility;
+mod click;
+mod redraw;
+
+use app::{AboutOptions, App, AppEvent, AppCommand};
 use window::{Window, WindowEvent, WindowCommand};
 use view::ViewEvent;
+use controls::ControlEvent;
 use servo::ServoEvent;
 use std::env::args;
 use servo::{Servo, ServoUrl};
@@ -46,6 +59,8 @@ fn main() {
     get_state().window_states.push(Window::get_init_state());
 
     let view = window.create_view().unwrap();
+    let controls = window.get_controls();
+    view.set_autoscroll_enabled(true);
 
     // Skip first argument (executable), and find the first
     // argument that doesn't start with `-`
@@ -65,6 +80,11 @@ fn main() {
 
     info!("Servo version: {}", servo.version());
 
+    app.set_about_options(Some(AboutOptions {
+        version: Some(servo.version()),
+        .. AboutOptions::default()
+    }));
+
     let handle_events = || {
 
         // Loop until no events are available anymore.
@@ -74,11 +94,13 @@ fn main() {
             let win_events = window.get_events();
             let view_events = view.get_events();
             let servo_events = servo.get_events();
+            let control_events = controls.get_events();
 
             if app_events.is_empty() &&
                win_events.is_empty() &&
                view_events.is_empty() &&
-               servo_events.is_empty() {
+               servo_events.is_empty() &&
+               control_events.is_empty() {
                    break
             }
 
@@ -99,11 +121,56 @@ fn main() {
                         servo.update_geometry(view.get_geometry());
                         view.update_drawable();
                     }
+                    AppEvent::ProxyIconDragged(..) => {
+                        // The URL is already on the drag pasteboard
+                    }
+                    AppEvent::WindowAppearanceChanged(..) => {
+                        // FIXME: forward to Servo once it supports prefers-color-scheme
+                    }
+                    AppEvent::AccessibilityDisplayOptionsChanged => {
+                        window.update_theme();
+                    }
+                    AppEvent::WindowDidBecomeKey(..) => {
+                        // FIXME: update current_window_index once we support multiple windows
+                    }
+                    AppEvent::MemoryPressure(..) => {
+                        // FIXME: ask Servo to release its caches
+                    }
+                    AppEvent::ScrollSettingsChanged(..) => {
+                        // FIXME: Servo's scroll physics aren't configurable
+                    }
+                    AppEvent::DisplayConnected(..) | AppEvent::DisplayDisconnected(..) => {
+                        // Only one window, AppKit moves it off removed displays
+                    }
+                    AppEvent::DidHide | AppEvent::DidUnhide => {
+                        // FIXME: pause/resume Servo's rendering
+                    }
+                    AppEvent::TabSelected(..) => {
+                        // FIXME: update current_window_index once we support multiple windows
+                    }
+                    AppEvent::Shortcut(..) => {
+                        // No custom tabs
+                    }
+                    AppEvent::MenuAction(..) => {
+                        // No custom menu items
+                    }
+                    AppEvent::RefreshRateChanged(..) => {
+                        // FIXME: Servo paces its frames on its own for now
+                    }
+                    AppEvent::OpenRecent(url) => {
+                        match ServoUrl::parse(&url) {
+                            Ok(url) => servo.load_url(url),
+                            Err(err) => warn!("Can't parse url: {}", err),
+                        }
+                    }
                     AppEvent::DoCommand(cmd) => {
                         match cmd {
                             AppCommand::ClearHistory => {
                                 // FIXME
                             }
+                            AppCommand::ClearRecent => {
+                                app.clear_recent();
+                            }
                             AppCommand::ToggleOptionDarkTheme => {
                                 ui_invalidated = true;
                                 get_state().dark_theme = !get_state().dark_theme;
@@ -123,15 +190,21 @@ fn main() {
                         servo.update_geometry(view.get_geometry());
                         view.update_drawable();
                     }
-                    WindowEvent::DidEnterFullScreen => {
-                        // FIXME
-                    }
+                    WindowEvent::DidEnterFullScreen |
                     WindowEvent::DidExitFullScreen => {
-                        // FIXME
+                        // The fullscreen aspect ratio only applies in fullscreen
+                        servo.update_geometry(view.get_geometry());
+                        view.update_drawable();
                     }
                     WindowEvent::WillClose => {
                         // FIXME
                     }
+                    WindowEvent::CloseRequested => {
+                        // Nothing to save
+                    }
+                    WindowEvent::DidChangeSpace => {
+                        // Nothing to do
+                    }
                     WindowEvent::DoCommand(cmd) => {
                         let ref mut state = get_state().window_states[0].browser_states[0];
                         match cmd {
@@ -224,13 +297,36 @@ fn main() {
                 }
             }
 
+            for event in control_events {
+                match event {
+                    ControlEvent::PermissionResponse(..) => {
+                        // FIXME: Servo doesn't ask for permissions yet
+                    }
+                }
+            }
+
             for event in view_events {
                 let ref mut state = get_state().window_states[0].browser_states[0];
                 match event {
                     ViewEvent::GeometryDidChange => {
+                        // While resizing, the last frame is stretched. We only
+                        // re-render once the resize is over.
+                        if !view.is_content_frozen() {
+                            servo.update_geometry(view.get_geometry());
+                            view.update_drawable();
+                        }
+                    }
+                    ViewEvent::LiveResizeStarted => {
+                        view.freeze_content();
+                    }
+                    ViewEvent::LiveResizeEnded => {
+                        view.thaw_content();
                         servo.update_geometry(view.get_geometry());
                         view.update_drawable();
                     }
+                    ViewEvent::EditAction(..) => {
+                        // FIXME: Servo doesn't support the clipboard yet
+                    }
                     ViewEvent::MouseWheel(delta, phase) => {
                         let (x, y) = match delta {
                             view::MouseScrollDelta::PixelDelta(x, y) => {
@@ -240,6 +336,35 @@ fn main() {
                         };
                         servo.perform_scroll(0, 0, x, y, phase);
                     }
+                    ViewEvent::Autoscroll(dx, dy) => {
+                        let (x, y) = state.last_mouse_point;
+                        servo.perform_scroll(x, y, -dx, -dy, view::TouchPhase::Moved);
+                    }
+                    ViewEvent::ClickCount(..) => {
+                        // Servo counts clicks on its own
+                    }
+                    ViewEvent::TextInput(text) => {
+                        servo.perform_text_input(&text);
+                    }
+                    ViewEvent::MarkedTextChanged(..) => {
+                        // FIXME: the composition isn't shown inline, only
+                        // its result reaches Servo
+                    }
+                    ViewEvent::ModifiersChanged(..) => {
+                        // FIXME: Servo gets modifiers with each key event, nothing to resync
+                    }
+                    ViewEvent::LockKeysChanged(..) => {
+                        // FIXME: Servo doesn't expose the lock keys state
+                    }
+                    ViewEvent::RedrawRequested => {
+                        // Servo schedules its own composites
+                    }
+                    ViewEvent::DisplayLinkFallback => {
+                        // Already logged
+                    }
+                    ViewEvent::QuickLook(..) => {
+                        // FIXME: Servo doesn't expose word lookup
+                    }
                     ViewEvent::MouseMoved(x, y) => {
                         state.last_mouse_point = (x, y);
                         servo.perform_mouse_move(x, y);
@@ -289,6 +414,7 @@ fn main() {
                         window.set_status(status);
                     }
                     ServoEvent::LoadStart => {
+                        controls.set_error_state(None);
                         state.is_loading = true;
                         ui_invalidated = true;
                     }
@@ -296,8 +422,8 @@ fn main() {
                         state.is_loading = false;
                         ui_invalidated = true;
                     }
-                    ServoEvent::LoadError(..) => {
-                        // FIXME
+                    ServoEvent::LoadError(url) => {
+                        controls.set_error_state(Some(&format!("Failed to load {}", url)));
                     }
                     ServoEvent::HeadParsed => {
                         // FIXME
@@ -306,6 +432,7 @@ fn main() {
                         let current = history.current;
                         let url = history.entries[current].url.to_string();
                         window.set_url(&url);
+                        app.note_recent_url(&url);
                         state.url = Some(url);
                         state.can_go_back = current > 0;
                         state.can_go_forward = current < history.entries.len() - 1;
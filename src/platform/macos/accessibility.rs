@@ -0,0 +1,155 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use accessibility::{AccessibilityNodeId, AccessibilityProvider, AccessibilityRole};
+use cocoa::base::*;
+use cocoa::foundation::*;
+use objc::declare::ClassDecl;
+use objc::runtime::{Class, Object, Sel};
+use std::os::raw::c_void;
+
+pub fn register() {
+    // One node of the AccessibilityProvider tree, as seen by NSAccessibility.
+    // Created on demand. Two elements of the same node are equal.
+    let superclass = Class::get("NSAccessibilityElement").unwrap();
+    let mut class = ClassDecl::new("NSShellAccessibilityElement", superclass).unwrap();
+    class.add_ivar::<id>("nsview");
+    class.add_ivar::<u64>("node");
+
+    extern fn is_accessibility_element(_this: &Object, _sel: Sel) -> BOOL {
+        YES
+    }
+
+    extern fn role(this: &Object, _sel: Sel) -> id {
+        let role = match with_provider(this, |provider, node| provider.role(node)) {
+            Some(AccessibilityRole::Group) | None => "AXGroup",
+            Some(AccessibilityRole::StaticText) => "AXStaticText",
+            Some(AccessibilityRole::Heading) => "AXHeading",
+            Some(AccessibilityRole::Link) => "AXLink",
+            Some(AccessibilityRole::Button) => "AXButton",
+            Some(AccessibilityRole::CheckBox) => "AXCheckBox",
+            Some(AccessibilityRole::TextField) => "AXTextField",
+            Some(AccessibilityRole::Image) => "AXImage",
+            Some(AccessibilityRole::List) => "AXList",
+        };
+        unsafe { NSString::alloc(nil).init_str(role).autorelease() }
+    }
+
+    extern fn label(this: &Object, _sel: Sel) -> id {
+        match with_provider(this, |provider, node| provider.label(node)) {
+            Some(Some(label)) => unsafe { NSString::alloc(nil).init_str(&label).autorelease() },
+            _ => nil,
+        }
+    }
+
+    extern fn value(this: &Object, _sel: Sel) -> id {
+        match with_provider(this, |provider, node| provider.value(node)) {
+            Some(Some(value)) => unsafe { NSString::alloc(nil).init_str(&value).autorelease() },
+            _ => nil,
+        }
+    }
+
+    extern fn children(this: &Object, _sel: Sel) -> id {
+        unsafe {
+            let nsview: id = *this.get_ivar("nsview");
+            let node: u64 = *this.get_ivar("node");
+            get_children(nsview, Some(AccessibilityNodeId(node)))
+        }
+    }
+
+    extern fn parent(this: &Object, _sel: Sel) -> id {
+        let nsview: id = unsafe { *this.get_ivar("nsview") };
+        match with_provider(this, |provider, node| provider.parent(node)) {
+            Some(Some(parent)) => get_element(nsview, parent),
+            _ => nsview,
+        }
+    }
+
+    extern fn is_focused(this: &Object, _sel: Sel) -> BOOL {
+        let focused = with_provider(this, |provider, node| provider.focused() == Some(node));
+        if focused == Some(true) { YES } else { NO }
+    }
+
+    // FIXME: the provider doesn't know about node geometry yet. Report the
+    // view frame so VoiceOver has something to highlight.
+    extern fn frame(this: &Object, _sel: Sel) -> NSRect {
+        unsafe {
+            let nsview: id = *this.get_ivar("nsview");
+            let bounds: NSRect = msg_send![nsview, bounds];
+            let in_window: NSRect = msg_send![nsview, convertRect:bounds toView:nil];
+            let nswindow: id = msg_send![nsview, window];
+            msg_send![nswindow, convertRectToScreen:in_window]
+        }
+    }
+
+    extern fn is_equal(this: &Object, _sel: Sel, other: id) -> BOOL {
+        unsafe {
+            let is_element: BOOL = msg_send![other, isKindOfClass:class("NSShellAccessibilityElement")];
+            if is_element == NO {
+                return NO;
+            }
+            let other = &*other;
+            let same_view = *this.get_ivar::<id>("nsview") == *other.get_ivar::<id>("nsview");
+            let same_node = *this.get_ivar::<u64>("node") == *other.get_ivar::<u64>("node");
+            if same_view && same_node { YES } else { NO }
+        }
+    }
+
+    extern fn hash(this: &Object, _sel: Sel) -> NSUInteger {
+        unsafe { *this.get_ivar::<u64>("node") as NSUInteger }
+    }
+
+    unsafe {
+        class.add_method(sel!(isAccessibilityElement), is_accessibility_element as extern fn(&Object, Sel) -> BOOL);
+        class.add_method(sel!(accessibilityRole), role as extern fn(&Object, Sel) -> id);
+        class.add_method(sel!(accessibilityLabel), label as extern fn(&Object, Sel) -> id);
+        class.add_method(sel!(accessibilityValue), value as extern fn(&Object, Sel) -> id);
+        class.add_method(sel!(accessibilityChildren), children as extern fn(&Object, Sel) -> id);
+        class.add_method(sel!(accessibilityParent), parent as extern fn(&Object, Sel) -> id);
+        class.add_method(sel!(isAccessibilityFocused), is_focused as extern fn(&Object, Sel) -> BOOL);
+        class.add_method(sel!(accessibilityFrame), frame as extern fn(&Object, Sel) -> NSRect);
+        class.add_method(sel!(isEqual:), is_equal as extern fn(&Object, Sel, id) -> BOOL);
+        class.add_method(sel!(hash), hash as extern fn(&Object, Sel) -> NSUInteger);
+    }
+
+    class.register();
+}
+
+/// The provider set with View::set_accessibility_provider, if any.
+pub fn get_provider<'a>(nsview: id) -> Option<&'a AccessibilityProvider> {
+    unsafe {
+        let ivar: *mut c_void = *(&*nsview).get_ivar("accessibility_provider");
+        if ivar.is_null() {
+            None
+        } else {
+            Some(&**(ivar as *mut Box<AccessibilityProvider>))
+        }
+    }
+}
+
+fn with_provider<F, T>(element: &Object, f: F) -> Option<T>
+    where F: FnOnce(&AccessibilityProvider, AccessibilityNodeId) -> T {
+    unsafe {
+        let nsview: id = *element.get_ivar("nsview");
+        let node: u64 = *element.get_ivar("node");
+        get_provider(nsview).map(|provider| f(provider, AccessibilityNodeId(node)))
+    }
+}
+
+/// Autoreleased element for this node.
+pub fn get_element(nsview: id, node: AccessibilityNodeId) -> id {
+    unsafe {
+        let element: id = msg_send![class("NSShellAccessibilityElement"), new];
+        (*element).set_ivar("nsview", nsview);
+        (*element).set_ivar("node", node.0);
+        element.autorelease()
+    }
+}
+
+/// Autoreleased NSArray of the elements below this node (None: the view).
+pub fn get_children(nsview: id, node: Option<AccessibilityNodeId>) -> id {
+    let nodes = get_provider(nsview).map(|provider| provider.children(node)).unwrap_or(Vec::new());
+    let elements: Vec<id> = nodes.into_iter().map(|node| get_element(nsview, node)).collect();
+    unsafe { NSArray::arrayWithObjects(nil, &elements) }
+}
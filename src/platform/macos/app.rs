@@ -7,17 +7,76 @@ use cocoa::base::*;
 use cocoa::foundation::*;
 use objc::declare::ClassDecl;
 use objc::runtime::{Class, Object, Sel};
+use std::cell::{Cell, RefCell};
 use std::os::raw::c_void;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering, ATOMIC_BOOL_INIT, ATOMIC_USIZE_INIT};
+use std::rc::Rc;
+use super::clipboard::Clipboard;
 use super::window;
 use super::utils;
-use app::{AppEvent, AppCommand};
+use super::dispatch::*;
+use super::screen;
+use priority;
+use super::view::{self, View};
+use window::{WindowOptions, STYLE_TITLED, STYLE_CLOSABLE, STYLE_MINIATURIZABLE, STYLE_RESIZABLE};
+use error::ShellError;
+use app::{AboutOptions, AppEvent, AppCommand, AppOptions, FrameStats, LatencySample, LatencyEventKind, PressureLevel};
+use app::ScrollSettings;
+use clock::{duration_to_secs, Clock};
+use screen::{DeviceMetrics, ScreenId};
 use state::AppState;
+use super::get_state;
+
+#[link(name = "AppKit", kind = "framework")]
+extern {
+    fn NSDisableScreenUpdates();
+    fn NSEnableScreenUpdates();
+}
+
+const MAX_RECENT_URLS: usize = 10;
+
+// Same as Window.nib
+const DEFAULT_WINDOW_WIDTH: f64 = 800.0;
+const DEFAULT_WINDOW_HEIGHT: f64 = 600.0;
+
+// NSDisableScreenUpdates calls made by the embedder not yet balanced
+static SCREEN_UPDATES_DISABLED: AtomicUsize = ATOMIC_USIZE_INIT;
+
+pub fn disable_screen_updates() {
+    SCREEN_UPDATES_DISABLED.fetch_add(1, Ordering::SeqCst);
+    unsafe { NSDisableScreenUpdates() };
+}
+
+pub fn enable_screen_updates() {
+    let disabled = SCREEN_UPDATES_DISABLED.load(Ordering::SeqCst);
+    if disabled == 0 {
+        warn!("enable_screen_updates called without disable_screen_updates");
+        return;
+    }
+    SCREEN_UPDATES_DISABLED.store(disabled - 1, Ordering::SeqCst);
+    unsafe { NSEnableScreenUpdates() };
+}
+
+// At the end of each run loop iteration, in case the embedder forgot
+fn enable_all_screen_updates() {
+    let disabled = SCREEN_UPDATES_DISABLED.swap(0, Ordering::SeqCst);
+    if disabled > 0 {
+        warn!("Screen updates still disabled at the end of the event loop iteration");
+        for _ in 0..disabled {
+            unsafe { NSEnableScreenUpdates() };
+        }
+    }
+}
 
 pub fn register() {
     let superclass = Class::get("NSResponder").unwrap();
     let mut class = ClassDecl::new("NSShellApplicationDelegate", superclass).unwrap();
     class.add_ivar::<*mut c_void>("event_queue");
     class.add_ivar::<*mut c_void>("state");
+    class.add_ivar::<*mut c_void>("latency");
+    // Box<ScrollSettings>, last reported
+    class.add_ivar::<*mut c_void>("scroll_settings");
 
     extern fn did_finish_launching(this: &Object, _sel: Sel, _notification: id) {
         utils::get_event_queue(this).push(AppEvent::DidFinishLaunching)
@@ -27,14 +86,73 @@ pub fn register() {
         utils::get_event_queue(this).push(AppEvent::DidChangeScreenParameters)
     }
 
+    // Modifiers released while another app was active never reached the view
+    extern fn did_become_active(_this: &Object, _sel: Sel, _notification: id) {
+        unsafe {
+            let nswindow: id = msg_send![NSApp(), keyWindow];
+            view::report_modifiers(nswindow);
+        }
+        view::sync_secure_event_input();
+    }
+
+    // Secure input would block the keyboard of the other apps
+    extern fn did_resign_active(_this: &Object, _sel: Sel, _notification: id) {
+        view::set_secure_event_input(false);
+    }
+
+    extern fn did_hide(this: &Object, _sel: Sel, _notification: id) {
+        utils::get_event_queue(this).push(AppEvent::DidHide)
+    }
+
+    extern fn did_unhide(this: &Object, _sel: Sel, _notification: id) {
+        utils::get_event_queue(this).push(AppEvent::DidUnhide)
+    }
+
     extern fn will_terminate(this: &Object, _sel: Sel, _notification: id) {
+        view::set_secure_event_input(false);
         utils::get_event_queue(this).push(AppEvent::WillTerminate)
     }
 
+    extern fn accessibility_display_options_did_change(this: &Object, _sel: Sel, _notification: id) {
+        utils::get_event_queue(this).push(AppEvent::AccessibilityDisplayOptionsChanged)
+    }
+
+    // Any defaults change, and the swipe direction one posted by the
+    // System Preferences. Only report actual scroll settings changes.
+    extern fn defaults_did_change(this: &Object, _sel: Sel, _notification: id) {
+        let settings = App::scroll_settings();
+        let last: &mut ScrollSettings = utils::get_ivar(this, "scroll_settings");
+        if *last != settings {
+            *last = settings;
+            utils::get_event_queue(this).push(AppEvent::ScrollSettingsChanged(settings))
+        }
+    }
+
+    extern fn open_recent(this: &Object, _sel: Sel, item: id) {
+        let url = unsafe {
+            let url: id = msg_send![item, representedObject];
+            utils::nsstring_to_string(url)
+        };
+        utils::get_event_queue(this).push(AppEvent::OpenRecent(url));
+    }
+
+    extern fn show_about(this: &Object, _sel: Sel, _item: id) {
+        match get_state().about_options {
+            Some(ref options) => show_about_panel(options),
+            None => utils::get_event_queue(this).push(AppEvent::MenuAction("about".to_owned())),
+        }
+    }
+
     extern fn validate_ui(_this: &Object, _sel: Sel, item: id) -> BOOL {
         let action: Sel = unsafe {msg_send![item, action]};
         if action == sel!(shellClearHistory:) {
             YES
+        } else if action == sel!(shellShowAbout:) {
+            YES
+        } else if action == sel!(shellOpenRecent:) {
+            YES
+        } else if action == sel!(shellClearRecent:) {
+            if get_state().recent_urls.is_empty() {NO} else {YES}
         } else if action == sel!(shellToggleOptionDarkTheme:) {
             YES
         } else {
@@ -46,6 +164,8 @@ pub fn register() {
         let action: Sel = unsafe {msg_send![item, action]};
         let cmd = if action == sel!(shellClearHistory:) {
             AppCommand::ClearHistory
+        } else if action == sel!(shellClearRecent:) {
+            AppCommand::ClearRecent
         } else if action == sel!(shellToggleOptionDarkTheme:) {
             AppCommand::ToggleOptionDarkTheme
         } else {
@@ -57,32 +177,175 @@ pub fn register() {
     unsafe {
         class.add_method(sel!(applicationDidFinishLaunching:), did_finish_launching as extern fn(&Object, Sel, id));
         class.add_method(sel!(applicationDidChangeScreenParameter:), did_change_screen_parameter as extern fn(&Object, Sel, id));
+        class.add_method(sel!(applicationDidBecomeActive:), did_become_active as extern fn(&Object, Sel, id));
+        class.add_method(sel!(applicationDidResignActive:), did_resign_active as extern fn(&Object, Sel, id));
+        class.add_method(sel!(applicationDidHide:), did_hide as extern fn(&Object, Sel, id));
+        class.add_method(sel!(applicationDidUnhide:), did_unhide as extern fn(&Object, Sel, id));
         class.add_method(sel!(applicationWillTerminate:), will_terminate as extern fn(&Object, Sel, id));
 
+        class.add_method(sel!(accessibilityDisplayOptionsDidChange:), accessibility_display_options_did_change as extern fn(&Object, Sel, id));
+
         class.add_method(sel!(validateUserInterfaceItem:), validate_ui as extern fn(&Object, Sel, id) -> BOOL);
 
         class.add_method(sel!(shellClearHistory:), record_command as extern fn(&Object, Sel, id));
         class.add_method(sel!(shellToggleOptionDarkTheme:), record_command as extern fn(&Object, Sel, id));
+        class.add_method(sel!(shellClearRecent:), record_command as extern fn(&Object, Sel, id));
+        class.add_method(sel!(shellDefaultsDidChange:), defaults_did_change as extern fn(&Object, Sel, id));
+        class.add_method(sel!(shellOpenRecent:), open_recent as extern fn(&Object, Sel, id));
+        class.add_method(sel!(shellShowAbout:), show_about as extern fn(&Object, Sel, id));
     }
 
     class.register();
 }
 
+// Queue an event from outside of the app delegate (window delegate, view…)
+pub fn push_event(event: AppEvent) {
+    unsafe {
+        let delegate: id = msg_send![NSApp(), delegate];
+        utils::get_event_queue(&*delegate).push(event);
+    }
+}
+
+// Set by note_activity, cleared by the run loop
+static ACTIVITY_NOTED: AtomicBool = ATOMIC_BOOL_INIT;
+
+// Like an input event: resume ticking if idle (see AppOptions::idle_after),
+// and wake up the run loop
+pub fn note_activity() {
+    ACTIVITY_NOTED.store(true, Ordering::SeqCst);
+    wake_up();
+}
+
+// Post an event, so the run loop returns and the event queues are processed
+pub fn wake_up() {
+    unsafe {
+        let event: id = msg_send![class("NSEvent"),
+                otherEventWithType:NSApplicationDefined
+                location:NSPoint::new(0.0, 0.0)
+                modifierFlags:NSEventModifierFlags::empty()
+                timestamp:0.0
+                windowNumber:0
+                context:nil
+                subtype:NSEventSubtype::NSApplicationActivatedEventType
+                data1:0
+                data2:0];
+        msg_send![NSApp(), postEvent:event atStart:NO];
+    }
+}
+
+// Runs on the main queue, so on the main thread
+extern fn memory_pressure_handler(source: *mut c_void) {
+    let level = unsafe { dispatch_source_get_data(source) };
+    let level = if level & DISPATCH_MEMORYPRESSURE_CRITICAL != 0 {
+        PressureLevel::Critical
+    } else if level & DISPATCH_MEMORYPRESSURE_WARN != 0 {
+        PressureLevel::Warning
+    } else {
+        PressureLevel::Normal
+    };
+    push_event(AppEvent::MemoryPressure(level));
+    wake_up();
+}
+
+extern fn display_reconfigured(display: screen::CGDirectDisplayID, flags: u32, _user_info: *mut c_void) {
+    if flags & screen::DISPLAY_BEGIN_CONFIGURATION_FLAG != 0 {
+        return;
+    }
+    if flags & screen::DISPLAY_ADD_FLAG != 0 {
+        push_event(AppEvent::DisplayConnected(ScreenId(display)));
+    } else if flags & screen::DISPLAY_REMOVE_FLAG != 0 {
+        push_event(AppEvent::DisplayDisconnected(ScreenId(display)));
+    } else {
+        return;
+    }
+    wake_up();
+}
+
+fn show_about_panel(options: &AboutOptions) {
+    unsafe {
+        let dict: id = msg_send![class("NSMutableDictionary"), dictionary];
+        let entries = [("ApplicationName", &options.application_name),
+                       ("ApplicationVersion", &options.application_version),
+                       ("Version", &options.version),
+                       ("Copyright", &options.copyright)];
+        for &(key, value) in entries.iter() {
+            if let Some(ref value) = *value {
+                let key = NSString::alloc(nil).init_str(key);
+                let value = NSString::alloc(nil).init_str(value);
+                msg_send![dict, setObject:value forKey:key];
+            }
+        }
+        if let Some(ref credits) = options.credits {
+            let key = NSString::alloc(nil).init_str("Credits");
+            let string = NSString::alloc(nil).init_str(credits);
+            let credits: id = msg_send![class("NSAttributedString"), alloc];
+            let credits: id = msg_send![credits, initWithString:string];
+            msg_send![dict, setObject:credits forKey:key];
+            msg_send![credits, release];
+        }
+        msg_send![NSApp(), orderFrontStandardAboutPanelWithOptions:dict];
+    }
+}
+
+struct LatencyTracker {
+    clock: Rc<Clock>,
+    hook: Option<Box<Fn(LatencySample)>>,
+    // Kind and timestamp of the oldest input not yet followed by a present
+    pending: Option<(LatencyEventKind, NSTimeInterval)>,
+    frame_stats_hook: Option<Box<Fn(FrameStats)>>,
+    // Accumulated since the last present, in seconds
+    waiting: NSTimeInterval,
+    in_callback: NSTimeInterval,
+    last_present: Option<NSTimeInterval>,
+}
+
+fn get_latency_tracker<'a>() -> &'a mut LatencyTracker {
+    unsafe {
+        let delegate: id = msg_send![NSApp(), delegate];
+        utils::get_ivar(&*delegate, "latency")
+    }
+}
+
+// Called by the view once a frame has been flushed to the screen.
+pub fn did_present() {
+    let tracker = get_latency_tracker();
+    if let Some((kind, timestamp)) = tracker.pending.take() {
+        if let Some(ref hook) = tracker.hook {
+            hook(LatencySample {
+                kind: kind,
+                ms: (tracker.clock.now() - timestamp) * 1000.0,
+            });
+        }
+    }
+    if let Some(ref hook) = tracker.frame_stats_hook {
+        let now = tracker.clock.now();
+        hook(FrameStats {
+            waiting_ms: tracker.waiting * 1000.0,
+            callback_ms: tracker.in_callback * 1000.0,
+            since_last_present_ms: tracker.last_present.map(|last| (now - last) * 1000.0),
+        });
+        tracker.waiting = 0.0;
+        tracker.in_callback = 0.0;
+        tracker.last_present = Some(now);
+    }
+}
 
 pub struct App {
-    nsapp: id
+    nsapp: id,
+    options: AppOptions,
+    last_activity: Cell<NSTimeInterval>,
+    clipboard_observer: RefCell<Option<Box<Fn()>>>,
+    clipboard_change_count: Cell<u64>,
+    memory_pressure_source: dispatch_source_t,
 }
 
 impl App {
 
     pub fn load() -> Result<App, &'static str> {
+        App::load_with_options(AppOptions::default())
+    }
 
-        let state = AppState {
-            current_window_index: None,
-            window_states: Vec::new(),
-            dark_theme: false,
-        };
-
+    pub fn load_with_options(options: AppOptions) -> Result<App, &'static str> {
         let instances = match utils::load_nib("App.nib") {
             Ok(instances) => instances,
             Err(msg) => return Err(msg),
@@ -97,6 +360,35 @@ impl App {
             Some(id) => id,
         };
 
+        Ok(App::finish_loading(nsapp, options))
+    }
+
+    /// Like `load`, but without App.nib: the main menu is built in code.
+    /// Windows are then expected to come from `create_window_programmatic`.
+    pub fn bootstrap() -> App {
+        App::bootstrap_with_options(AppOptions::default())
+    }
+
+    pub fn bootstrap_with_options(options: AppOptions) -> App {
+        let nsapp: id = unsafe { msg_send![class("NSApplication"), sharedApplication] };
+        unsafe {
+            let menu = build_main_menu(nsapp);
+            msg_send![nsapp, setMainMenu:menu];
+        }
+        App::finish_loading(nsapp, options)
+    }
+
+    // Whatever App.nib doesn't do
+    fn finish_loading(nsapp: id, options: AppOptions) -> App {
+
+        let state = AppState {
+            current_window_index: None,
+            window_states: Vec::new(),
+            dark_theme: false,
+            recent_urls: Vec::new(),
+            about_options: Some(AboutOptions::default()),
+        };
+
         unsafe {
             nsapp.setActivationPolicy_(NSApplicationActivationPolicyRegular);
             let current_app = NSRunningApplication::currentApplication(nil);
@@ -109,16 +401,210 @@ impl App {
 
         let state_ptr = Box::into_raw(Box::new(state));
 
+        let latency = LatencyTracker {
+            clock: options.clock.clone(),
+            hook: None,
+            pending: None,
+            frame_stats_hook: None,
+            waiting: 0.0,
+            in_callback: 0.0,
+            last_present: None,
+        };
+        let latency_ptr = Box::into_raw(Box::new(latency));
+        let scroll_settings_ptr = Box::into_raw(Box::new(App::scroll_settings()));
+
         unsafe {
             let delegate: id = msg_send![class("NSShellApplicationDelegate"), alloc];
             (*delegate).set_ivar("event_queue", event_queue_ptr as *mut c_void);
             (*delegate).set_ivar("state", state_ptr as *mut c_void);
+            (*delegate).set_ivar("latency", latency_ptr as *mut c_void);
+            (*delegate).set_ivar("scroll_settings", scroll_settings_ptr as *mut c_void);
             msg_send![nsapp, setDelegate:delegate];
+
+            let center: id = msg_send![class("NSNotificationCenter"), defaultCenter];
+            let name = NSString::alloc(nil).init_str("NSUserDefaultsDidChangeNotification");
+            msg_send![center, addObserver:delegate selector:sel!(shellDefaultsDidChange:) name:name object:nil];
+            let center: id = msg_send![class("NSDistributedNotificationCenter"), defaultCenter];
+            let name = NSString::alloc(nil).init_str("SwipeScrollDirectionDidChangeNotification");
+            msg_send![center, addObserver:delegate selector:sel!(shellDefaultsDidChange:) name:name object:nil];
+
+            let workspace: id = msg_send![class("NSWorkspace"), sharedWorkspace];
+            let center: id = msg_send![workspace, notificationCenter];
+            let name = NSString::alloc(nil).init_str("NSWorkspaceAccessibilityDisplayOptionsDidChangeNotification");
+            msg_send![center, addObserver:delegate
+                                 selector:sel!(accessibilityDisplayOptionsDidChange:)
+                                     name:name
+                                   object:nil];
+        }
+
+        let memory_pressure_source = unsafe {
+            let mask = DISPATCH_MEMORYPRESSURE_NORMAL | DISPATCH_MEMORYPRESSURE_WARN | DISPATCH_MEMORYPRESSURE_CRITICAL;
+            let source = dispatch_source_create(dispatch_source_type_memorypressure(), 0, mask, dispatch_get_main_queue());
+            dispatch_set_context(source, source);
+            dispatch_source_set_event_handler_f(source, memory_pressure_handler);
+            dispatch_resume(source);
+            source
+        };
+
+        unsafe {
+            screen::CGDisplayRegisterReconfigurationCallback(display_reconfigured, ptr::null_mut());
+        }
+
+        let now = options.clock.now();
+        let app = App {
+            nsapp: nsapp,
+            options: options,
+            last_activity: Cell::new(now),
+            clipboard_observer: RefCell::new(None),
+            clipboard_change_count: Cell::new(0),
+            memory_pressure_source: memory_pressure_source,
+        };
+
+        app
+    }
+
+    /// Whether the "Reduce transparency" accessibility option is on.
+    pub fn reduce_transparency() -> bool {
+        unsafe {
+            let workspace: id = msg_send![class("NSWorkspace"), sharedWorkspace];
+            let reduce: BOOL = msg_send![workspace, accessibilityDisplayShouldReduceTransparency];
+            reduce == YES
+        }
+    }
+
+    pub fn show_about_panel(&self, options: AboutOptions) {
+        show_about_panel(&options);
+    }
+
+    /// What the About menu item shows. None to handle it yourself: the
+    /// menu item then sends AppEvent::MenuAction("about").
+    pub fn set_about_options(&self, options: Option<AboutOptions>) {
+        get_state().about_options = options;
+    }
+
+    /// Add `url` at the top of the Open Recent menu. Picking it sends
+    /// AppEvent::OpenRecent. Not using NSDocumentController, as it only
+    /// knows about file URLs.
+    pub fn note_recent_url(&self, url: &str) {
+        {
+            let ref mut urls = get_state().recent_urls;
+            urls.retain(|u| u != url);
+            urls.insert(0, url.to_owned());
+            urls.truncate(MAX_RECENT_URLS);
         }
+        self.rebuild_recent_menu();
+    }
 
-        let app = App {nsapp: nsapp};
+    pub fn clear_recent(&self) {
+        get_state().recent_urls.clear();
+        self.rebuild_recent_menu();
+    }
 
-        Ok(app)
+    fn rebuild_recent_menu(&self) {
+        unsafe {
+            let menu = match App::get_recent_menu(self.nsapp) {
+                Some(menu) => menu,
+                None => {
+                    warn!("Couldn't find the Open Recent menu");
+                    return;
+                }
+            };
+            // The URLs are listed above the separator and Clear Menu items
+            loop {
+                let index: NSInteger = msg_send![menu, indexOfItemWithTarget:nil andAction:sel!(shellOpenRecent:)];
+                if index < 0 {
+                    break;
+                }
+                msg_send![menu, removeItemAtIndex:index];
+            }
+            for (index, url) in get_state().recent_urls.iter().enumerate() {
+                let title = NSString::alloc(nil).init_str(url);
+                let key = NSString::alloc(nil).init_str("");
+                let item: id = msg_send![menu, insertItemWithTitle:title
+                                                            action:sel!(shellOpenRecent:)
+                                                     keyEquivalent:key
+                                                           atIndex:index as NSInteger];
+                msg_send![item, setRepresentedObject:title];
+            }
+        }
+    }
+
+    // The submenu holding the Clear Menu item
+    fn get_recent_menu(nsapp: id) -> Option<id> {
+        unsafe {
+            let main_menu: id = msg_send![nsapp, mainMenu];
+            let count: NSInteger = msg_send![main_menu, numberOfItems];
+            for i in 0..count {
+                let item: id = msg_send![main_menu, itemAtIndex:i];
+                let menu: id = msg_send![item, submenu];
+                if menu == nil {
+                    continue;
+                }
+                let subcount: NSInteger = msg_send![menu, numberOfItems];
+                for j in 0..subcount {
+                    let subitem: id = msg_send![menu, itemAtIndex:j];
+                    let submenu: id = msg_send![subitem, submenu];
+                    if submenu == nil {
+                        continue;
+                    }
+                    let index: NSInteger = msg_send![submenu, indexOfItemWithTarget:nil andAction:sel!(shellClearRecent:)];
+                    if index >= 0 {
+                        return Some(submenu);
+                    }
+                }
+            }
+            None
+        }
+    }
+
+    /// Make `menu` the Window menu: AppKit fills it with one item per
+    /// window (Minimize, Zoom, Bring All to Front are expected to be
+    /// there already). By default, that's the Window menu of App.nib.
+    pub fn set_windows_menu(&self, menu: id) {
+        unsafe { msg_send![self.nsapp, setWindowsMenu:menu] };
+    }
+
+    /// Same as the Hide ServoShell menu item (Cmd+H). DidHide follows.
+    pub fn hide(&self) {
+        unsafe { msg_send![self.nsapp, hide:nil] };
+    }
+
+    /// DidUnhide follows.
+    pub fn unhide(&self) {
+        unsafe { msg_send![self.nsapp, unhide:nil] };
+    }
+
+    /// Same as the Hide Others menu item (Alt+Cmd+H).
+    pub fn hide_other_apps(&self) {
+        unsafe { msg_send![self.nsapp, hideOtherApplications:nil] };
+    }
+
+    /// Scrolling preferences, read from the global defaults domain. See
+    /// `ScrollSettings` for the keys. ScrollSettingsChanged is sent when
+    /// they change.
+    pub fn scroll_settings() -> ScrollSettings {
+        unsafe {
+            let defaults: id = msg_send![class("NSUserDefaults"), standardUserDefaults];
+            // Changes made by other processes to the global domain
+            msg_send![defaults, synchronize];
+            let key = NSString::alloc(nil).init_str("com.apple.scrollwheel.scaling");
+            let scaling: id = msg_send![defaults, objectForKey:key];
+            let scaling: f64 = if scaling == nil { 0.3125 } else { msg_send![scaling, doubleValue] };
+            let key = NSString::alloc(nil).init_str("com.apple.swipescrolldirection");
+            let natural: id = msg_send![defaults, objectForKey:key];
+            let natural: BOOL = if natural == nil { YES } else { msg_send![natural, boolValue] };
+            ScrollSettings {
+                speed: scaling.max(0.0).min(5.0) as f32,
+                acceleration: scaling >= 0.0,
+                natural: natural == YES,
+            }
+        }
+    }
+
+    /// Pointer and screen characteristics, for the pointer, hover and
+    /// resolution media queries.
+    pub fn device_metrics() -> DeviceMetrics {
+        screen::device_metrics()
     }
 
     pub fn state_changed(&self) {
@@ -126,12 +612,109 @@ impl App {
         // updated via validate_ui
     }
 
+    /// Ordered by priority, see `priority::Priority`.
     pub fn get_events(&self) -> Vec<AppEvent> {
         let nsobject = unsafe {
             let delegate: id = msg_send![self.nsapp, delegate];
             &*delegate
         };
-        utils::get_event_queue(nsobject).drain(..).collect()
+        priority::sort_by_priority(utils::get_event_queue(nsobject).drain(..).collect())
+    }
+
+    /// Report the time between an input event and the next frame presented
+    /// on screen. Nothing is recorded as long as no hook is set.
+    pub fn set_latency_hook(&self, hook: Box<Fn(LatencySample)>) {
+        get_latency_tracker().hook = Some(hook);
+    }
+
+    /// Report, for each presented frame, how long the run loop waited for
+    /// events, how long the `run` callback took, and the time since the
+    /// previous frame. Nothing is measured as long as no hook is set.
+    pub fn set_frame_stats_hook(&self, hook: Box<Fn(FrameStats)>) {
+        get_latency_tracker().frame_stats_hook = Some(hook);
+    }
+
+    /// Called when the content of the system clipboard changes. The change
+    /// count is checked every time the event loop wakes up.
+    pub fn set_clipboard_observer(&self, observer: Box<Fn()>) {
+        self.clipboard_change_count.set(Clipboard::change_count());
+        *self.clipboard_observer.borrow_mut() = Some(observer);
+    }
+
+    fn poll_clipboard(&self) {
+        if let Some(ref observer) = *self.clipboard_observer.borrow() {
+            let count = Clipboard::change_count();
+            if count != self.clipboard_change_count.get() {
+                self.clipboard_change_count.set(count);
+                observer();
+            }
+        }
+    }
+
+    fn record_input(&self, nsevent: id) {
+        let tracker = get_latency_tracker();
+        if tracker.hook.is_none() || tracker.pending.is_some() {
+            return
+        }
+        let kind = unsafe {
+            match nsevent.eventType() {
+                NSLeftMouseDown | NSLeftMouseUp |
+                NSRightMouseDown | NSRightMouseUp |
+                NSOtherMouseDown | NSOtherMouseUp => LatencyEventKind::MouseInput,
+                NSMouseMoved | NSLeftMouseDragged |
+                NSRightMouseDragged | NSOtherMouseDragged => LatencyEventKind::MouseMoved,
+                NSScrollWheel => LatencyEventKind::MouseWheel,
+                NSKeyDown | NSKeyUp => LatencyEventKind::Key,
+                _ => return,
+            }
+        };
+        // When the event is dequeued, not when the window server sent it,
+        // so both ends of the measurement come from the same clock.
+        tracker.pending = Some((kind, tracker.clock.now()));
+    }
+
+    // With a tick interval, wake up regularly, unless nothing happened
+    // for `idle_after`. Then only an event or note_activity can wake us up.
+    fn wakeup_date(&self) -> id {
+        let interval = match self.options.tick_interval {
+            Some(interval) => interval,
+            None => return unsafe { NSDate::distantFuture(nil) },
+        };
+        if let Some(idle_after) = self.options.idle_after {
+            if ACTIVITY_NOTED.swap(false, Ordering::SeqCst) {
+                self.last_activity.set(self.options.clock.now());
+            }
+            if self.options.clock.now() - self.last_activity.get() > duration_to_secs(idle_after) {
+                return unsafe { NSDate::distantFuture(nil) };
+            }
+        }
+        unsafe {
+            msg_send![class("NSDate"), dateWithTimeIntervalSinceNow:duration_to_secs(interval)]
+        }
+    }
+
+    fn event_mask(&self) -> NSUInteger {
+        match self.options.event_mask {
+            // We rely on NSApplicationDefined events for wake_up
+            Some(mask) => mask as NSUInteger | NSApplicationDefinedMask.bits(),
+            None => NSAnyEventMask.bits(),
+        }
+    }
+
+    // Periodic events (see startPeriodicEventsAfterDelay:withPeriod:) are
+    // meant for tracking loops and never reach sendEvent: handlers.
+    fn forward_periodic_event(nsevent: id) {
+        unsafe {
+            let nswindow: id = msg_send![NSApp(), keyWindow];
+            if nswindow == nil {
+                return;
+            }
+            let responder: id = msg_send![nswindow, firstResponder];
+            let responds: BOOL = msg_send![responder, respondsToSelector:sel!(shellPeriodicEvent:)];
+            if responds == YES {
+                msg_send![responder, shellPeriodicEvent:nsevent];
+            }
+        }
     }
 
     // Equivalent of NSApp.run()
@@ -143,47 +726,155 @@ impl App {
             unsafe {
                 let pool = NSAutoreleasePool::new(nil);
 
-                // Blocks until event available
+                let timed = get_latency_tracker().frame_stats_hook.is_some();
+                let wait_start = if timed { self.options.clock.now() } else { 0.0 };
+
+                // Blocks until event available (or until next tick)
                 let nsevent = self.nsapp.nextEventMatchingMask_untilDate_inMode_dequeue_(
-                    NSAnyEventMask.bits(),
-                    NSDate::distantFuture(nil), NSDefaultRunLoopMode, YES);
-
-                let event_type = nsevent.eventType() as u64;
-                if event_type == NSApplicationDefined as u64 {
-                    let event_subtype = nsevent.subtype() as i16;
-                    if event_subtype == NSEventSubtype::NSApplicationActivatedEventType as i16 {
-                        let nswindow: id = msg_send![nsevent, window];
-                        msg_send![nswindow, eventLoopRised];
+                    self.event_mask(),
+                    self.wakeup_date(), NSDefaultRunLoopMode, YES);
+
+                if timed {
+                    get_latency_tracker().waiting += self.options.clock.now() - wait_start;
+                }
+
+                if nsevent != nil {
+                    self.last_activity.set(self.options.clock.now());
+                    let event_type = nsevent.eventType() as u64;
+                    if event_type == NSApplicationDefined as u64 {
+                        let event_subtype = nsevent.subtype() as i16;
+                        if event_subtype == NSEventSubtype::NSApplicationActivatedEventType as i16 {
+                            let nswindow: id = msg_send![nsevent, window];
+                            msg_send![nswindow, eventLoopRised];
+                        }
+                    } else if event_type == NSPeriodic as u64 {
+                        App::forward_periodic_event(nsevent);
+                    } else {
+                        self.record_input(nsevent);
+                        msg_send![self.nsapp, sendEvent: nsevent];
                     }
-                } else {
-                    msg_send![self.nsapp, sendEvent: nsevent];
                 }
 
                 // Get all pending events
                 loop {
                     let nsevent = self.nsapp.nextEventMatchingMask_untilDate_inMode_dequeue_(
-                        NSAnyEventMask.bits(),
+                        self.event_mask(),
                         NSDate::distantPast(nil), NSDefaultRunLoopMode, YES);
-                    msg_send![self.nsapp, sendEvent: nsevent];
                     if nsevent == nil {
                         break;
                     }
+                    self.last_activity.set(self.options.clock.now());
+                    if nsevent.eventType() as u64 == NSPeriodic as u64 {
+                        App::forward_periodic_event(nsevent);
+                    } else {
+                        self.record_input(nsevent);
+                        msg_send![self.nsapp, sendEvent: nsevent];
+                    }
                 }
 
                 msg_send![self.nsapp, updateWindows];
                 msg_send![pool, release];
             }
-            callback();
+            self.poll_clipboard();
+            if get_latency_tracker().frame_stats_hook.is_some() {
+                let start = self.options.clock.now();
+                callback();
+                get_latency_tracker().in_callback += self.options.clock.now() - start;
+            } else {
+                callback();
+            }
+            enable_all_screen_updates();
         }
     }
 
-    pub fn create_window(&self) -> Result<window::Window, &'static str> {
+    pub fn create_window(&self) -> Result<window::Window, ShellError> {
         let (nswindow, nspopover) = match App::create_native_window() {
             Ok(w) => w,
-            Err(msg) => return Err(msg),
+            Err(msg) => return Err(ShellError::Nib(msg)),
+        };
+
+        Ok(window::Window::new(nswindow, nspopover, self.options.clock.clone()))
+    }
+
+    /// Like `create_window`, but with the initial size, position, title and
+    /// style taken from `opts`. The view hierarchy still comes from Window.nib.
+    pub fn create_window_with(&self, opts: WindowOptions) -> Result<(window::Window, View), ShellError> {
+        // The nib window shows up as soon as it's instantiated. Don't let
+        // it hit the screen before it has its final geometry.
+        unsafe { NSDisableScreenUpdates() };
+
+        let (nswindow, nspopover) = match App::create_native_window() {
+            Ok(w) => w,
+            Err(msg) => {
+                unsafe { NSEnableScreenUpdates() };
+                return Err(ShellError::Nib(msg))
+            }
+        };
+
+        window::apply_options(nswindow, &opts);
+
+        unsafe { NSEnableScreenUpdates() };
+
+        let window = window::Window::new(nswindow, nspopover, self.options.clock.clone());
+        let view = match window.create_view() {
+            Ok(view) => view,
+            Err(err) => return Err(err),
         };
 
-        Ok(window::Window::new(nswindow, nspopover))
+        Ok((window, view))
+    }
+
+    /// Like `create_window_with`, but without Window.nib: just a window
+    /// and its Servo view. There's no toolbar, sidebar, logs or status bar,
+    /// so the Window methods driving them do nothing.
+    pub fn create_window_programmatic(&self, opts: WindowOptions) -> Result<(window::Window, View), ShellError> {
+        let nswindow = unsafe {
+            let rect = NSRect::new(NSPoint::new(0.0, 0.0),
+                                   NSSize::new(DEFAULT_WINDOW_WIDTH, DEFAULT_WINDOW_HEIGHT));
+            let mask = window::style_to_mask(STYLE_TITLED | STYLE_CLOSABLE |
+                                             STYLE_MINIATURIZABLE | STYLE_RESIZABLE);
+            let backing: NSUInteger = 2; // NSBackingStoreBuffered
+            let nswindow: id = msg_send![class("NSShellWindow"), alloc];
+            let nswindow: id = msg_send![nswindow, initWithContentRect:rect
+                                                             styleMask:mask
+                                                               backing:backing
+                                                                 defer:NO];
+            msg_send![nswindow, setReleasedWhenClosed:NO];
+            msg_send![nswindow, awakeFromNib];
+            msg_send![nswindow, center];
+
+            let nsview: id = msg_send![class("NSServoView"), alloc];
+            let nsview: id = msg_send![nsview, initWithFrame:rect];
+            let identifier = NSString::alloc(nil).init_str("shellViewServo");
+            msg_send![nsview, setIdentifier:identifier];
+            msg_send![nsview, setWantsLayer:YES];
+            // NSViewWidthSizable | NSViewHeightSizable
+            msg_send![nsview, setAutoresizingMask:18 as NSUInteger];
+            msg_send![nsview, awakeFromNib];
+            msg_send![nswindow, setContentView:nsview];
+            nswindow
+        };
+
+        window::apply_options(nswindow, &opts);
+
+        let nspopover: id = unsafe {
+            let nspopover: id = msg_send![class("NSPopover"), alloc];
+            msg_send![nspopover, init]
+        };
+
+        let window = window::Window::new(nswindow, nspopover, self.options.clock.clone());
+        // Window.nib relies on the toolbar to show the title
+        unsafe {
+            nswindow.setTitleVisibility_(NSWindowTitleVisibility::NSWindowTitleVisible);
+        }
+        let view = match window.create_view() {
+            Ok(view) => view,
+            Err(err) => return Err(err),
+        };
+
+        unsafe { msg_send![nswindow, makeKeyAndOrderFront:nil] };
+
+        Ok((window, view))
     }
 
     fn create_native_window() -> Result<(id, id), &'static str> {
@@ -218,3 +909,101 @@ impl App {
         Ok((nswindow, nspopover))
     }
 }
+
+// A trimmed down version of the App.nib menu, for App::bootstrap. The
+// actions go to the first responder, as in the nib.
+unsafe fn build_main_menu(nsapp: id) -> id {
+    let main_menu = new_menu("");
+
+    let app_menu = new_menu("ServoShell");
+    add_menu_item(app_menu, "About ServoShell", sel!(shellShowAbout:), "", None);
+    add_menu_separator(app_menu);
+    add_menu_item(app_menu, "Hide ServoShell", sel!(hide:), "h", None);
+    add_menu_item(app_menu, "Hide Others", sel!(hideOtherApplications:), "h",
+                  Some(NSAlternateKeyMask | NSCommandKeyMask));
+    add_menu_item(app_menu, "Show All", sel!(unhideAllApplications:), "", None);
+    add_menu_separator(app_menu);
+    add_menu_item(app_menu, "Quit ServoShell", sel!(terminate:), "q", None);
+    add_submenu(main_menu, app_menu);
+
+    let file_menu = new_menu("File");
+    add_menu_item(file_menu, "Open Location", sel!(shellOpenLocation:), "l", None);
+    // Found by get_recent_menu through its Clear Menu item
+    let recent_menu = new_menu("Open Recent");
+    add_menu_item(recent_menu, "Clear Menu", sel!(shellClearRecent:), "", None);
+    add_submenu(file_menu, recent_menu);
+    add_menu_separator(file_menu);
+    add_menu_item(file_menu, "Close Tab", sel!(performClose:), "w", None);
+    add_submenu(main_menu, file_menu);
+
+    let edit_menu = new_menu("Edit");
+    add_menu_item(edit_menu, "Undo", sel!(undo:), "z", None);
+    add_menu_item(edit_menu, "Redo", sel!(redo:), "Z", None);
+    add_menu_separator(edit_menu);
+    add_menu_item(edit_menu, "Cut", sel!(cut:), "x", None);
+    add_menu_item(edit_menu, "Copy", sel!(copy:), "c", None);
+    add_menu_item(edit_menu, "Paste", sel!(paste:), "v", None);
+    add_menu_item(edit_menu, "Select All", sel!(selectAll:), "a", None);
+    add_submenu(main_menu, edit_menu);
+
+    let view_menu = new_menu("View");
+    add_menu_item(view_menu, "Enter Full Screen", sel!(toggleFullScreen:), "f",
+                  Some(NSControlKeyMask | NSCommandKeyMask));
+    add_menu_separator(view_menu);
+    add_menu_item(view_menu, "Stop", sel!(shellStop:), ".", None);
+    add_menu_item(view_menu, "Reload", sel!(shellReload:), "r", None);
+    add_menu_separator(view_menu);
+    add_menu_item(view_menu, "Actual Size", sel!(shellZoomToActualSize:), "0", None);
+    add_menu_item(view_menu, "Zoom In", sel!(shellZoomIn:), "=", None);
+    add_menu_item(view_menu, "Zoom Out", sel!(shellZoomOut:), "-", None);
+    add_submenu(main_menu, view_menu);
+
+    let window_menu = new_menu("Window");
+    add_menu_item(window_menu, "Minimize", sel!(performMiniaturize:), "m", None);
+    add_menu_item(window_menu, "Zoom", sel!(performZoom:), "", None);
+    add_menu_separator(window_menu);
+    add_menu_item(window_menu, "Bring All to Front", sel!(arrangeInFront:), "", None);
+    add_submenu(main_menu, window_menu);
+    msg_send![nsapp, setWindowsMenu:window_menu];
+
+    main_menu
+}
+
+unsafe fn new_menu(title: &str) -> id {
+    let title = NSString::alloc(nil).init_str(title);
+    let menu: id = msg_send![class("NSMenu"), alloc];
+    msg_send![menu, initWithTitle:title]
+}
+
+// Without a mask, the key equivalent comes with Cmd (and Shift if uppercase)
+unsafe fn add_menu_item(menu: id, title: &str, action: Sel, key: &str, mask: Option<NSEventModifierFlags>) {
+    let title = NSString::alloc(nil).init_str(title);
+    let key = NSString::alloc(nil).init_str(key);
+    let item: id = msg_send![menu, addItemWithTitle:title action:action keyEquivalent:key];
+    if let Some(mask) = mask {
+        msg_send![item, setKeyEquivalentModifierMask:mask];
+    }
+}
+
+unsafe fn add_menu_separator(menu: id) {
+    let item: id = msg_send![class("NSMenuItem"), separatorItem];
+    msg_send![menu, addItem:item];
+}
+
+unsafe fn add_submenu(menu: id, submenu: id) {
+    let title: id = msg_send![submenu, title];
+    let key = NSString::alloc(nil).init_str("");
+    let item: id = msg_send![menu, addItemWithTitle:title action:nil keyEquivalent:key];
+    msg_send![menu, setSubmenu:submenu forItem:item];
+}
+
+impl Drop for App {
+    fn drop(&mut self) {
+        unsafe {
+            dispatch_source_cancel(self.memory_pressure_source);
+            dispatch_release(self.memory_pressure_source);
+            screen::CGDisplayRemoveReconfigurationCallback(display_reconfigured, ptr::null_mut());
+        }
+        view::set_secure_event_input(false);
+    }
+}
@@ -0,0 +1,102 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use cocoa::base::*;
+use cocoa::foundation::*;
+use error::ShellError;
+use std::os::raw::c_void;
+use std::slice;
+
+const UTI_PLAIN_TEXT: &'static str = "public.utf8-plain-text";
+
+pub struct Clipboard {
+    pasteboard: id,
+}
+
+impl Clipboard {
+    pub fn general() -> Clipboard {
+        unsafe {
+            Clipboard {
+                pasteboard: msg_send![class("NSPasteboard"), generalPasteboard],
+            }
+        }
+    }
+
+    /// Incremented by the system every time the content of the general
+    /// pasteboard changes.
+    pub fn change_count() -> u64 {
+        unsafe {
+            let pasteboard: id = msg_send![class("NSPasteboard"), generalPasteboard];
+            let count: NSInteger = msg_send![pasteboard, changeCount];
+            count as u64
+        }
+    }
+
+    /// Raw data of the first pasteboard item that has a representation
+    /// of this type.
+    pub fn read_type(&self, uti: &str) -> Option<Vec<u8>> {
+        if !is_valid_uti(uti) {
+            return None;
+        }
+        unsafe {
+            let items: id = msg_send![self.pasteboard, pasteboardItems];
+            if items == nil {
+                return None;
+            }
+            let uti = NSString::alloc(nil).init_str(uti);
+            let count: NSUInteger = msg_send![items, count];
+            for i in 0..count {
+                let item: id = msg_send![items, objectAtIndex:i];
+                let data: id = msg_send![item, dataForType:uti];
+                if data != nil {
+                    let length: NSUInteger = msg_send![data, length];
+                    let bytes: *const u8 = msg_send![data, bytes];
+                    if length == 0 || bytes.is_null() {
+                        return Some(Vec::new());
+                    }
+                    return Some(slice::from_raw_parts(bytes, length as usize).to_vec());
+                }
+            }
+            None
+        }
+    }
+
+    /// Replace the content of the pasteboard with one item holding one
+    /// representation per (type, data) pair.
+    pub fn write_types(&self, items: &[(String, Vec<u8>)]) -> Result<(), ShellError> {
+        if let Some(&(ref uti, _)) = items.iter().find(|&&(ref uti, _)| !is_valid_uti(uti)) {
+            return Err(ShellError::InvalidPasteboardType(uti.clone()));
+        }
+        unsafe {
+            let item: id = msg_send![class("NSPasteboardItem"), new];
+            for &(ref uti, ref bytes) in items {
+                let data: id = msg_send![class("NSData"),
+                                         dataWithBytes:bytes.as_ptr() as *const c_void
+                                         length:bytes.len() as NSUInteger];
+                let uti = NSString::alloc(nil).init_str(uti);
+                msg_send![item, setData:data forType:uti];
+            }
+            let objects: id = msg_send![class("NSArray"), arrayWithObject:item];
+            msg_send![self.pasteboard, clearContents];
+            msg_send![self.pasteboard, writeObjects:objects];
+            msg_send![item, release];
+        }
+        Ok(())
+    }
+
+    pub fn read_text(&self) -> Option<String> {
+        self.read_type(UTI_PLAIN_TEXT).and_then(|bytes| String::from_utf8(bytes).ok())
+    }
+
+    pub fn write_text(&self, text: &str) {
+        // UTI_PLAIN_TEXT is valid, this can't fail
+        let _ = self.write_types(&[(UTI_PLAIN_TEXT.to_owned(), text.as_bytes().to_vec())]);
+    }
+}
+
+// UTIs are reverse-DNS strings: "public.png", "com.example.web-custom-format"
+fn is_valid_uti(uti: &str) -> bool {
+    !uti.is_empty() && !uti.starts_with('.') && !uti.ends_with('.') && !uti.contains("..") &&
+    uti.chars().all(|c| (c as u32) < 0x80 && (c.is_alphanumeric() || c == '.' || c == '-'))
+}
@@ -0,0 +1,20 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use cocoa::base::*;
+use cocoa::foundation::NSTimeInterval;
+use clock::Clock;
+
+/// Seconds since boot. Same time base as NSEvent timestamps.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> f64 {
+        unsafe {
+            let info: id = msg_send![class("NSProcessInfo"), processInfo];
+            let uptime: NSTimeInterval = msg_send![info, systemUptime];
+            uptime
+        }
+    }
+}
@@ -0,0 +1,392 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use cocoa::base::*;
+use cocoa::foundation::*;
+use controls::{ControlEvent, ControlsUpdate, PermissionKind, SecurityState};
+use objc::declare::ClassDecl;
+use objc::runtime::{Class, Object, Sel};
+use super::utils;
+use std::os::raw::c_void;
+use std::ptr;
+
+pub fn register() {
+    // Target of the prompt buttons, and delegate of its popover
+    let superclass = Class::get("NSObject").unwrap();
+    let mut class = ClassDecl::new("NSShellPermissionPrompt", superclass).unwrap();
+    class.add_ivar::<id>("nswindow");
+    class.add_ivar::<id>("popover");
+    class.add_ivar::<u8>("kind");
+    class.add_ivar::<BOOL>("answered");
+
+    extern fn respond(this: &mut Object, sel: Sel, _sender: id) {
+        let granted = sel == sel!(shellPermissionAllow:);
+        unsafe {
+            this.set_ivar("answered", YES);
+            let nswindow: id = *this.get_ivar("nswindow");
+            let popover: id = *this.get_ivar("popover");
+            let kind = u8_to_permission_kind(*this.get_ivar("kind"));
+            get_control_event_queue(nswindow).push(ControlEvent::PermissionResponse(kind, granted));
+            msg_send![popover, performClose:nil];
+        }
+    }
+
+    extern fn popover_did_close(this: &mut Object, _sel: Sel, _notification: id) {
+        unsafe {
+            let answered: BOOL = *this.get_ivar("answered");
+            let nswindow: id = *this.get_ivar("nswindow");
+            let popover: id = *this.get_ivar("popover");
+            if answered == NO {
+                let kind = u8_to_permission_kind(*this.get_ivar("kind"));
+                get_control_event_queue(nswindow).push(ControlEvent::PermissionResponse(kind, false));
+            }
+            msg_send![popover, setDelegate:nil];
+            msg_send![popover, release];
+            msg_send![this, release];
+        }
+    }
+
+    unsafe {
+        class.add_method(sel!(shellPermissionAllow:), respond as extern fn(&mut Object, Sel, id));
+        class.add_method(sel!(shellPermissionDeny:), respond as extern fn(&mut Object, Sel, id));
+        class.add_method(sel!(popoverDidClose:), popover_did_close as extern fn(&mut Object, Sel, id));
+    }
+
+    class.register();
+}
+
+/// Whether this point (window coordinates) is in one of the rects set with
+/// Controls::set_non_draggable_subregions.
+pub fn is_in_non_draggable_subregion(nswindow: &Object, point: NSPoint) -> bool {
+    unsafe {
+        let ivar: *mut c_void = *nswindow.get_ivar("non_draggable_subregions");
+        if ivar.is_null() {
+            return false;
+        }
+        let rects = &*(ivar as *mut Vec<(f32, f32, f32, f32)>);
+        // Rects are from the top left
+        let frame: NSRect = msg_send![nswindow, frame];
+        let x = point.x as f32;
+        let y = (frame.size.height - point.y) as f32;
+        rects.iter().any(|&(rx, ry, width, height)| {
+            x >= rx && x < rx + width && y >= ry && y < ry + height
+        })
+    }
+}
+
+const URLBAR_SECURITY: &'static str = "shellToolbarViewUrlbarSecurity";
+const URLBAR_FAVICON: &'static str = "shellToolbarViewUrlbarFavicon";
+const URLBAR_ICON_SIZE: CGFloat = 16.0;
+// Leading space of the urlbar textfield in the nib, and between icons
+const URLBAR_ICON_SPACING: CGFloat = 2.0;
+
+// Decoded by NSImage. nil if it can't.
+unsafe fn image_from_data(data: &[u8]) -> id {
+    let nsdata: id = msg_send![class("NSData"),
+                               dataWithBytes:data.as_ptr() as *const c_void
+                               length:data.len() as NSUInteger];
+    let image: id = msg_send![class("NSImage"), alloc];
+    let image: id = msg_send![image, initWithData:nsdata];
+    if image == nil {
+        warn!("Couldn't decode the favicon");
+        return nil;
+    }
+    msg_send![image, autorelease]
+}
+
+// Icon views are created on first use, and hidden when there's no image
+unsafe fn set_urlbar_icon(urlbar: id, identifier: &'static str, image: id, tooltip: Option<&str>) {
+    let view = match utils::get_view_by_id(urlbar, identifier) {
+        Some(view) => view,
+        None if image == nil => return,
+        None => {
+            let frame = NSRect::new(NSPoint::new(0., 0.), NSSize::new(URLBAR_ICON_SIZE, URLBAR_ICON_SIZE));
+            let view: id = msg_send![class("NSImageView"), alloc];
+            let view: id = msg_send![view, initWithFrame:frame];
+            let name = NSString::alloc(nil).init_str(identifier);
+            msg_send![view, setIdentifier:name];
+            msg_send![view, setImageScaling:3 as NSUInteger]; // NSImageScaleProportionallyUpOrDown
+            msg_send![urlbar, addSubview:view];
+            msg_send![view, release];
+            view
+        }
+    };
+    msg_send![view, setImage:image];
+    msg_send![view, setHidden:if image == nil {YES} else {NO}];
+    match tooltip {
+        Some(tooltip) if image != nil => {
+            let tooltip = NSString::alloc(nil).init_str(tooltip);
+            msg_send![view, setToolTip:tooltip];
+        }
+        _ => msg_send![view, setToolTip:nil],
+    }
+}
+
+// Lock first, then favicon, then the textfield
+unsafe fn layout_urlbar_icons(urlbar: id, field: id) {
+    let bounds: NSRect = msg_send![urlbar, bounds];
+    let y = (bounds.size.height - URLBAR_ICON_SIZE) / 2.0;
+    let mut x = URLBAR_ICON_SPACING;
+    for identifier in [URLBAR_SECURITY, URLBAR_FAVICON].iter() {
+        if let Some(view) = utils::get_view_by_id(urlbar, *identifier) {
+            let hidden: BOOL = msg_send![view, isHidden];
+            if hidden == NO {
+                let frame = NSRect::new(NSPoint::new(x, y), NSSize::new(URLBAR_ICON_SIZE, URLBAR_ICON_SIZE));
+                msg_send![view, setFrame:frame];
+                x += URLBAR_ICON_SIZE + URLBAR_ICON_SPACING;
+            }
+        }
+    }
+    // Push the textfield by moving its leading constraint
+    let constraints: id = msg_send![urlbar, constraints];
+    let count: NSUInteger = msg_send![constraints, count];
+    for i in 0..count {
+        let constraint: id = msg_send![constraints, objectAtIndex:i];
+        let item: id = msg_send![constraint, firstItem];
+        let attribute: NSInteger = msg_send![constraint, firstAttribute];
+        if item == field && attribute == 5 { // NSLayoutAttributeLeading
+            msg_send![constraint, setConstant:x];
+        }
+    }
+}
+
+fn get_control_event_queue<'a>(nswindow: id) -> &'a mut Vec<ControlEvent> {
+    utils::get_ivar(unsafe { &*nswindow }, "control_event_queue")
+}
+
+fn permission_kind_to_u8(kind: PermissionKind) -> u8 {
+    match kind {
+        PermissionKind::Camera => 0,
+        PermissionKind::Microphone => 1,
+        PermissionKind::Geolocation => 2,
+        PermissionKind::Notifications => 3,
+    }
+}
+
+fn u8_to_permission_kind(kind: u8) -> PermissionKind {
+    match kind {
+        0 => PermissionKind::Camera,
+        1 => PermissionKind::Microphone,
+        2 => PermissionKind::Geolocation,
+        _ => PermissionKind::Notifications,
+    }
+}
+
+/// The browser chrome living in the window toolbar (urlbar and friends).
+pub struct Controls {
+    nswindow: id,
+}
+
+impl Controls {
+    pub fn new(nswindow: id) -> Controls {
+        Controls {
+            nswindow: nswindow,
+        }
+    }
+
+    /// Show that the page failed to load: the urlbar text turns red, and
+    /// the error is shown as its tooltip. `None` restores the regular style.
+    /// Not related to the security state of the page.
+    pub fn set_error_state(&self, error: Option<&str>) {
+        self.update(|update| {
+            update.set_error_state(error);
+        });
+    }
+
+    /// Apply several changes with a single relayout and redisplay of the
+    /// toolbar, instead of one per change.
+    pub fn update<F: FnOnce(&mut ControlsUpdate)>(&self, f: F) {
+        let mut update = ControlsUpdate::default();
+        f(&mut update);
+        let urlbar = match self.get_urlbar() {
+            Some(urlbar) => urlbar,
+            None => return,
+        };
+        let field = match utils::get_view_by_id(urlbar, "shellToolbarViewUrlbarTextfield") {
+            Some(field) => field,
+            None => return,
+        };
+        unsafe {
+            msg_send![self.nswindow, disableFlushWindow];
+            if let Some(ref url) = update.url {
+                let string = NSString::alloc(nil).init_str(url);
+                msg_send![field, setStringValue:string];
+            }
+            match update.error_state {
+                Some(Some(ref error)) => {
+                    let color: id = msg_send![class("NSColor"), systemRedColor];
+                    let tooltip = NSString::alloc(nil).init_str(error);
+                    msg_send![field, setTextColor:color];
+                    msg_send![field, setToolTip:tooltip];
+                }
+                Some(None) => {
+                    let color: id = msg_send![class("NSColor"), controlTextColor];
+                    msg_send![field, setTextColor:color];
+                    msg_send![field, setToolTip:nil];
+                }
+                None => {}
+            }
+            let indicator = utils::get_view_by_id(urlbar, "shellToolbarViewUrlbarThrobber");
+            if let (Some(loading), Some(indicator)) = (update.loading, indicator) {
+                if loading {
+                    msg_send![indicator, startAnimation:nil];
+                } else {
+                    msg_send![indicator, stopAnimation:nil];
+                }
+            }
+            if let (Some(progress), Some(indicator)) = (update.progress, indicator) {
+                match progress {
+                    Some(progress) => {
+                        // maxValue is 100 in the nib
+                        msg_send![indicator, setIndeterminate:NO];
+                        msg_send![indicator, setDisplayedWhenStopped:YES];
+                        msg_send![indicator, setDoubleValue:(progress * 100.0) as f64];
+                    }
+                    None => {
+                        msg_send![indicator, setIndeterminate:YES];
+                        msg_send![indicator, setDisplayedWhenStopped:NO];
+                    }
+                }
+            }
+            if let Some(ref favicon) = update.favicon {
+                let image = match *favicon {
+                    Some(ref data) => image_from_data(data),
+                    None => nil,
+                };
+                set_urlbar_icon(urlbar, URLBAR_FAVICON, image, None);
+            }
+            if let Some(state) = update.security_state {
+                let (name, tooltip) = match state {
+                    Some(SecurityState::Secure) => ("NSLockLockedTemplate", "Secure connection"),
+                    Some(SecurityState::Insecure) => ("NSLockUnlockedTemplate", "Not secure"),
+                    Some(SecurityState::Broken) => ("NSLockUnlockedTemplate", "Invalid certificate or mixed content"),
+                    None => ("", ""),
+                };
+                let image: id = if state.is_some() {
+                    let name = NSString::alloc(nil).init_str(name);
+                    msg_send![class("NSImage"), imageNamed:name]
+                } else {
+                    nil
+                };
+                set_urlbar_icon(urlbar, URLBAR_SECURITY, image, Some(tooltip));
+            }
+            if update.favicon.is_some() || update.security_state.is_some() {
+                layout_urlbar_icons(urlbar, field);
+            }
+            // The toolbar lives in the frame view, above the content view
+            let content_view: id = msg_send![self.nswindow, contentView];
+            let frame_view: id = msg_send![content_view, superview];
+            msg_send![frame_view, layoutSubtreeIfNeeded];
+            msg_send![self.nswindow, enableFlushWindow];
+            msg_send![self.nswindow, flushWindowIfNeeded];
+        }
+    }
+
+    /// Parts of the draggable chrome (titlebar, toolbar) where a click must
+    /// not start a window drag, like buttons drawn by the embedder.
+    /// (x, y, width, height), in points from the top left of the window.
+    /// Dragging anywhere else in the chrome still moves the window.
+    pub fn set_non_draggable_subregions(&self, rects: &[(f32, f32, f32, f32)]) {
+        unsafe {
+            let nswindow = &mut *self.nswindow;
+            let previous: *mut c_void = *nswindow.get_ivar("non_draggable_subregions");
+            let ptr = if rects.is_empty() {
+                ptr::null_mut()
+            } else {
+                Box::into_raw(Box::new(rects.to_vec())) as *mut c_void
+            };
+            nswindow.set_ivar("non_draggable_subregions", ptr);
+            if !previous.is_null() {
+                drop(Box::from_raw(previous as *mut Vec<(f32, f32, f32, f32)>));
+            }
+        }
+    }
+
+    pub fn get_events(&self) -> Vec<ControlEvent> {
+        get_control_event_queue(self.nswindow).drain(..).collect()
+    }
+
+    /// Ask the user, in a popover under the urlbar, whether `origin` can use
+    /// `kind`. The answer comes as ControlEvent::PermissionResponse.
+    /// Remembering the answer is up to the embedder.
+    pub fn show_permission_prompt(&self, kind: PermissionKind, origin: &str) {
+        let field = match self.get_urlbar_textfield() {
+            Some(field) => field,
+            None => return,
+        };
+        let what = match kind {
+            PermissionKind::Camera => "use your camera",
+            PermissionKind::Microphone => "use your microphone",
+            PermissionKind::Geolocation => "know your location",
+            PermissionKind::Notifications => "show notifications",
+        };
+        unsafe {
+            let prompt: id = msg_send![class("NSShellPermissionPrompt"), alloc];
+            let prompt: id = msg_send![prompt, init];
+
+            let content: id = msg_send![class("NSView"), alloc];
+            let content: id = msg_send![content, initWithFrame:NSRect::new(NSPoint::new(0., 0.), NSSize::new(300., 92.))];
+
+            let label: id = msg_send![class("NSTextField"), alloc];
+            let label: id = msg_send![label, initWithFrame:NSRect::new(NSPoint::new(16., 48.), NSSize::new(268., 32.))];
+            let text = NSString::alloc(nil).init_str(&format!("{} wants to {}", origin, what));
+            msg_send![label, setStringValue:text];
+            msg_send![label, setEditable:NO];
+            msg_send![label, setSelectable:NO];
+            msg_send![label, setBordered:NO];
+            msg_send![label, setDrawsBackground:NO];
+            msg_send![content, addSubview:label];
+            msg_send![label, release];
+
+            let buttons = [("Don't Allow", sel!(shellPermissionDeny:), 112., ""),
+                           ("Allow", sel!(shellPermissionAllow:), 204., "\r")];
+            for &(title, action, x, key) in buttons.iter() {
+                let button: id = msg_send![class("NSButton"), alloc];
+                let button: id = msg_send![button, initWithFrame:NSRect::new(NSPoint::new(x, 12.), NSSize::new(84., 28.))];
+                let title = NSString::alloc(nil).init_str(title);
+                let key = NSString::alloc(nil).init_str(key);
+                msg_send![button, setTitle:title];
+                msg_send![button, setBezelStyle:1 as NSUInteger]; // NSRoundedBezelStyle
+                msg_send![button, setKeyEquivalent:key];
+                msg_send![button, setTarget:prompt];
+                msg_send![button, setAction:action];
+                msg_send![content, addSubview:button];
+                msg_send![button, release];
+            }
+
+            let controller: id = msg_send![class("NSViewController"), alloc];
+            let controller: id = msg_send![controller, init];
+            msg_send![controller, setView:content];
+            msg_send![content, release];
+
+            let popover: id = msg_send![class("NSPopover"), alloc];
+            let popover: id = msg_send![popover, init];
+            msg_send![popover, setContentViewController:controller];
+            msg_send![controller, release];
+            msg_send![popover, setBehavior:1 as NSInteger]; // NSPopoverBehaviorTransient
+            msg_send![popover, setDelegate:prompt];
+
+            // Both released in popoverDidClose:
+            (*prompt).set_ivar("nswindow", self.nswindow);
+            (*prompt).set_ivar("popover", popover);
+            (*prompt).set_ivar("kind", permission_kind_to_u8(kind));
+            (*prompt).set_ivar("answered", NO);
+
+            let bounds: NSRect = msg_send![field, bounds];
+            msg_send![popover, showRelativeToRect:bounds ofView:field preferredEdge:3 as NSUInteger];
+        }
+    }
+
+    fn get_urlbar(&self) -> Option<id> {
+        utils::get_toolbar_item(self.nswindow, "urlbar").map(|item| {
+            unsafe { msg_send![item, view] }
+        })
+    }
+
+    fn get_urlbar_textfield(&self) -> Option<id> {
+        self.get_urlbar().and_then(|view| {
+            utils::get_view_by_id(view, "shellToolbarViewUrlbarTextfield")
+        })
+    }
+}
@@ -0,0 +1,46 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+// The bits of libdispatch we need. Part of libSystem, no need to link it.
+
+#![allow(non_camel_case_types, non_upper_case_globals)]
+
+use std::os::raw::{c_ulong, c_void};
+
+pub type dispatch_object_t = *mut c_void;
+pub type dispatch_queue_t = *mut c_void;
+pub type dispatch_source_t = *mut c_void;
+pub type dispatch_source_type_t = *const c_void;
+pub type dispatch_function_t = extern fn(*mut c_void);
+
+pub const DISPATCH_MEMORYPRESSURE_NORMAL: c_ulong = 0x01;
+pub const DISPATCH_MEMORYPRESSURE_WARN: c_ulong = 0x02;
+pub const DISPATCH_MEMORYPRESSURE_CRITICAL: c_ulong = 0x04;
+
+extern {
+    static _dispatch_main_q: c_void;
+    static _dispatch_source_type_memorypressure: c_void;
+
+    pub fn dispatch_source_create(type_: dispatch_source_type_t,
+                                  handle: c_ulong,
+                                  mask: c_ulong,
+                                  queue: dispatch_queue_t) -> dispatch_source_t;
+    pub fn dispatch_source_set_event_handler_f(source: dispatch_source_t, handler: dispatch_function_t);
+    pub fn dispatch_source_get_data(source: dispatch_source_t) -> c_ulong;
+    pub fn dispatch_source_cancel(source: dispatch_source_t);
+    pub fn dispatch_set_context(object: dispatch_object_t, context: *mut c_void);
+    pub fn dispatch_resume(object: dispatch_object_t);
+    pub fn dispatch_release(object: dispatch_object_t);
+    pub fn dispatch_async_f(queue: dispatch_queue_t, context: *mut c_void, work: dispatch_function_t);
+}
+
+// dispatch_get_main_queue() is a macro
+pub fn dispatch_get_main_queue() -> dispatch_queue_t {
+    unsafe { &_dispatch_main_q as *const _ as dispatch_queue_t }
+}
+
+// DISPATCH_SOURCE_TYPE_MEMORYPRESSURE is a macro
+pub fn dispatch_source_type_memorypressure() -> dispatch_source_type_t {
+    unsafe { &_dispatch_source_type_memorypressure as *const _ as dispatch_source_type_t }
+}
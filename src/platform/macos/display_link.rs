@@ -0,0 +1,31 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+// The bits of CoreVideo we need for CVDisplayLink
+
+#![allow(non_camel_case_types)]
+
+use std::os::raw::c_void;
+
+pub type CVDisplayLinkRef = *mut c_void;
+pub type CVReturn = i32;
+pub type CVDisplayLinkOutputCallback = extern fn(link: CVDisplayLinkRef,
+                                                 now: *const c_void,
+                                                 output_time: *const c_void,
+                                                 flags_in: u64,
+                                                 flags_out: *mut u64,
+                                                 context: *mut c_void) -> CVReturn;
+
+pub const K_CV_RETURN_SUCCESS: CVReturn = 0;
+
+#[link(name = "CoreVideo", kind = "framework")]
+extern {
+    pub fn CVDisplayLinkCreateWithActiveCGDisplays(link: *mut CVDisplayLinkRef) -> CVReturn;
+    pub fn CVDisplayLinkSetOutputCallback(link: CVDisplayLinkRef,
+                                          callback: CVDisplayLinkOutputCallback,
+                                          context: *mut c_void) -> CVReturn;
+    pub fn CVDisplayLinkStart(link: CVDisplayLinkRef) -> CVReturn;
+    pub fn CVDisplayLinkStop(link: CVDisplayLinkRef) -> CVReturn;
+    pub fn CVDisplayLinkRelease(link: CVDisplayLinkRef);
+}
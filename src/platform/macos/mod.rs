@@ -9,6 +9,13 @@ mod view;
 mod logs;
 mod toolbar;
 mod bookmarks;
+mod clipboard;
+mod controls;
+mod clock;
+mod dispatch;
+mod screen;
+mod display_link;
+mod accessibility;
 
 use std::env;
 use std::sync::{Once, ONCE_INIT};
@@ -24,6 +31,10 @@ pub use self::window::Window;
 pub use self::window::EventLoopRiser;
 pub use self::view::View;
 pub use self::logs::Logger;
+pub use self::clipboard::Clipboard;
+pub use self::controls::Controls;
+pub use self::clock::SystemClock;
+pub use self::screen::Screen;
 
 static INIT: Once = ONCE_INIT;
 
@@ -34,6 +45,8 @@ pub fn init() {
         toolbar::register();
         window::register();
         bookmarks::register();
+        controls::register();
+        accessibility::register();
     });
 }
 
@@ -0,0 +1,131 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+#![allow(non_camel_case_types)]
+
+use cocoa::base::*;
+use cocoa::foundation::*;
+use screen::{DeviceMetrics, Pointer, ScreenId};
+use std::ffi::CString;
+use std::os::raw::{c_char, c_void};
+
+pub type CGDirectDisplayID = u32;
+// Called on the main thread, once with DISPLAY_BEGIN_CONFIGURATION_FLAG
+// before the change, then with what changed.
+pub type CGDisplayReconfigurationCallBack = extern fn(display: CGDirectDisplayID, flags: u32, user_info: *mut c_void);
+
+// CGDisplayChangeSummaryFlags
+pub const DISPLAY_BEGIN_CONFIGURATION_FLAG: u32 = 1 << 0;
+pub const DISPLAY_ADD_FLAG: u32 = 1 << 4;
+pub const DISPLAY_REMOVE_FLAG: u32 = 1 << 5;
+type io_object_t = u32;
+type io_iterator_t = u32;
+
+#[link(name = "CoreGraphics", kind = "framework")]
+extern {
+    fn CGMainDisplayID() -> CGDirectDisplayID;
+    // From the EDID. Zero if not available.
+    fn CGDisplayScreenSize(display: CGDirectDisplayID) -> NSSize;
+    fn CGDisplayCopyDisplayMode(display: CGDirectDisplayID) -> *mut c_void;
+    fn CGDisplayModeGetRefreshRate(mode: *mut c_void) -> f64;
+    fn CGDisplayModeRelease(mode: *mut c_void);
+    pub fn CGDisplayRegisterReconfigurationCallback(callback: CGDisplayReconfigurationCallBack, user_info: *mut c_void) -> i32;
+    pub fn CGDisplayRemoveReconfigurationCallback(callback: CGDisplayReconfigurationCallBack, user_info: *mut c_void) -> i32;
+}
+
+#[link(name = "IOKit", kind = "framework")]
+extern {
+    fn IOServiceMatching(name: *const c_char) -> *mut c_void;
+    // Consumes the matching dictionary
+    fn IOServiceGetMatchingServices(master_port: u32, matching: *mut c_void, existing: *mut io_iterator_t) -> i32;
+    fn IOIteratorNext(iterator: io_iterator_t) -> io_object_t;
+    fn IOObjectRelease(object: io_object_t) -> i32;
+}
+
+// kIOMasterPortDefault
+const IO_MASTER_PORT_DEFAULT: u32 = 0;
+
+// Mice, trackpads and tablets all are IOHIDPointing services
+fn has_pointing_device() -> bool {
+    unsafe {
+        let name = CString::new("IOHIDPointing").unwrap();
+        let matching = IOServiceMatching(name.as_ptr());
+        let mut iterator: io_iterator_t = 0;
+        if IOServiceGetMatchingServices(IO_MASTER_PORT_DEFAULT, matching, &mut iterator) != 0 {
+            // Can't tell. Assume a desktop setup.
+            return true;
+        }
+        let service = IOIteratorNext(iterator);
+        let found = service != 0;
+        if found {
+            IOObjectRelease(service);
+        }
+        IOObjectRelease(iterator);
+        found
+    }
+}
+
+pub fn device_metrics() -> DeviceMetrics {
+    let size = unsafe { CGDisplayScreenSize(CGMainDisplayID()) };
+    // No touchscreen on macOS: a pointing device is a fine, hovering one.
+    let has_pointer = has_pointing_device();
+    DeviceMetrics {
+        pointer: if has_pointer { Pointer::Fine } else { Pointer::None },
+        hover: has_pointer,
+        screen_mm: (size.width as f32, size.height as f32),
+    }
+}
+
+// Built-in panels don't always report a refresh rate
+const DEFAULT_REFRESH_RATE: f32 = 60.0;
+
+pub struct Screen {
+    nsscreen: id,
+}
+
+impl Screen {
+    pub fn new(nsscreen: id) -> Screen {
+        Screen {
+            nsscreen: nsscreen,
+        }
+    }
+
+    pub fn main() -> Screen {
+        Screen::new(unsafe { msg_send![class("NSScreen"), mainScreen] })
+    }
+
+    pub fn id(&self) -> ScreenId {
+        unsafe {
+            let description: id = msg_send![self.nsscreen, deviceDescription];
+            let key = NSString::alloc(nil).init_str("NSScreenNumber");
+            let number: id = msg_send![description, objectForKey:key];
+            let display: u32 = msg_send![number, unsignedIntValue];
+            ScreenId(display)
+        }
+    }
+
+    /// In Hz. For variable refresh rate displays, the maximum.
+    pub fn refresh_rate(&self) -> f32 {
+        let ScreenId(display) = self.id();
+        let mut rate = unsafe {
+            let mode = CGDisplayCopyDisplayMode(display);
+            if mode.is_null() {
+                0.0
+            } else {
+                let rate = CGDisplayModeGetRefreshRate(mode) as f32;
+                CGDisplayModeRelease(mode);
+                rate
+            }
+        };
+        unsafe {
+            // 10.15+. Accounts for ProMotion displays.
+            let responds: BOOL = msg_send![self.nsscreen, respondsToSelector:sel!(maximumFramesPerSecond)];
+            if responds == YES {
+                let max: NSInteger = msg_send![self.nsscreen, maximumFramesPerSecond];
+                rate = rate.max(max as f32);
+            }
+        }
+        if rate > 0.0 { rate } else { DEFAULT_REFRESH_RATE }
+    }
+}
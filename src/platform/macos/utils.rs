@@ -72,6 +72,29 @@ pub fn get_classname(id: id) -> String {
     }
 }
 
+pub fn nsstring_to_string(string: id) -> String {
+    unsafe {
+        let string: *const libc::c_char = msg_send![string, UTF8String];
+        CStr::from_ptr(string).to_string_lossy().into_owned()
+    }
+}
+
+pub fn get_toolbar_item(nswindow: id, identifier: &str) -> Option<id> {
+    unsafe {
+        let toolbar: id = msg_send![nswindow, toolbar];
+        let items: id = msg_send![toolbar, items];
+        let count: NSInteger = msg_send![items, count];
+        for i in 0..count {
+            let item: id = msg_send![items, objectAtIndex:i];
+            let item_identifier: id = msg_send![item, itemIdentifier];
+            if NSString::isEqualToString(item_identifier, identifier) {
+                return Some(item);
+            }
+        }
+        None
+    }
+}
+
 pub fn get_view_by_id(id: id, name: &'static str) -> Option<id> {
     let nsview: id = if id_is_instance_of(id, "NSWindow") {
         unsafe { msg_send![id, contentView] }
@@ -86,6 +109,31 @@ pub fn get_view_by_id(id: id, name: &'static str) -> Option<id> {
     })
 }
 
+// One line per view: class name and identifier, indented by depth
+pub fn describe_view_hierarchy(nsview: id) -> String {
+    let mut description = String::new();
+    describe_view(nsview, 0, &mut description);
+    description
+}
+
+fn describe_view(nsview: id, depth: usize, description: &mut String) {
+    unsafe {
+        let identifier: id = msg_send![nsview, identifier];
+        let identifier = if identifier == nil {
+            "-".to_owned()
+        } else {
+            nsstring_to_string(identifier)
+        };
+        description.push_str(&format!("{}{} ({})\n", "  ".repeat(depth), get_classname(nsview), identifier));
+        let subviews: id = msg_send![nsview, subviews];
+        let count: NSInteger = msg_send![subviews, count];
+        for i in 0..count {
+            let view: id = msg_send![subviews, objectAtIndex:i];
+            describe_view(view, depth + 1, description);
+        }
+    }
+}
+
 pub fn get_view<F>(nsview: id, predicate: &F) -> Option<id> where F: Fn(id) -> bool {
     if predicate(nsview) {
         return Some(nsview);
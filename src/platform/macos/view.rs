@@ -13,15 +13,41 @@ use cocoa::appkit::*;
 use cocoa::foundation::*;
 use cocoa::base::*;
 use objc::declare::ClassDecl;
-use objc::runtime::{Class, Object, Sel};
-use self::cgl::{CGLEnable, kCGLCECrashOnRemovedFunctions};
+use objc::runtime::{Class, Object, Protocol, Sel};
+use self::cgl::{CGLEnable, CGLDisable, CGLSetParameter, kCGLCECrashOnRemovedFunctions};
+use self::cgl::{CGLContextEnable, CGLContextParameter};
 use self::core_foundation::base::TCFType;
 use self::core_foundation::string::CFString;
 use self::core_foundation::bundle::{CFBundleGetBundleWithIdentifier, CFBundleGetFunctionPointerForName};
+use std::cell::{Cell, RefCell};
 use std::os::raw::c_void;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering, ATOMIC_BOOL_INIT};
 use std::str::FromStr;
-use view::{ElementState, MouseButton, ViewEvent, TouchPhase, MouseScrollDelta};
+use view::{ActionSource, DragOperation, EditAction, Elasticity, ElementState, MouseButton, ViewEvent, TouchPhase, MouseScrollDelta, TextInputType};
+use view::{ImageData, LockKeys, Modifiers};
 use super::utils;
+use priority;
+use super::app;
+use super::dispatch::{dispatch_async_f, dispatch_get_main_queue};
+use super::display_link::*;
+use super::screen::Screen;
+use super::accessibility;
+use accessibility::AccessibilityProvider;
+use clock::Clock;
+use click::ClickCounter;
+use redraw::{RedrawAction, RedrawThrottle};
+use std::rc::Rc;
+use std::time::Duration;
+
+// Drag a selection closer than that (in points) to an edge to autoscroll
+const AUTOSCROLL_EDGE: CGFloat = 20.0;
+const AUTOSCROLL_DELAY: f64 = 0.1;
+const AUTOSCROLL_PERIOD: f64 = 0.05;
+
+// Not exposed by the cgl crate
+const K_CGL_CP_SURFACE_BACKING_SIZE: CGLContextParameter = 304;
+const K_CGL_CE_SURFACE_BACKING_SIZE: CGLContextEnable = 305;
 
 pub fn register() {
     let superclass = Class::get("NSView").unwrap();
@@ -29,8 +55,40 @@ pub fn register() {
 
     class.add_ivar::<*mut c_void>("event_queue");
     class.add_ivar::<*mut c_void>("live_resize_callback");
+    class.add_ivar::<BOOL>("shift_scroll_horizontal");
+    class.add_ivar::<NSUInteger>("drag_operation");
+    class.add_ivar::<BOOL>("text_input_disabled");
+    class.add_ivar::<u32>("render_width");
+    class.add_ivar::<u32>("render_height");
+    class.add_ivar::<NSInteger>("horizontal_elasticity");
+    class.add_ivar::<NSInteger>("vertical_elasticity");
+    class.add_ivar::<BOOL>("autoscroll_enabled");
+    class.add_ivar::<BOOL>("autoscrolling");
+    class.add_ivar::<BOOL>("opaque");
+    class.add_ivar::<BOOL>("momentum_active");
+    class.add_ivar::<*mut c_void>("frame_callback");
+    class.add_ivar::<*mut c_void>("display_link");
+    class.add_ivar::<id>("display_timer");
+    class.add_ivar::<BOOL>("display_link_fallback_reported");
+    class.add_ivar::<BOOL>("caps_lock");
+    class.add_ivar::<BOOL>("suppress_beep");
+    class.add_ivar::<id>("tooltip");
+    class.add_ivar::<*mut c_void>("accessibility_provider");
+    class.add_ivar::<BOOL>("secure_input");
+    // Box<Vec<(i32, i32, u32, u32)>>, see set_selection_rects
+    class.add_ivar::<*mut c_void>("selection_rects");
+    // Box<ClickCounter>, when NSEvent doesn't have a clickCount
+    class.add_ivar::<*mut c_void>("click_counter");
+    // Box<Rc<Clock>>, the AppOptions clock, set by View::new
+    class.add_ivar::<*mut c_void>("clock");
+    // See set_ime_candidate_position
+    class.add_ivar::<BOOL>("ime_position_set");
+    class.add_ivar::<i32>("ime_position_x");
+    class.add_ivar::<i32>("ime_position_y");
+    // NSString being composed by the input method, retained. nil if none.
+    class.add_ivar::<id>("marked_text");
 
-    extern fn store_nsevent(this: &Object, _sel: Sel, nsevent: id) {
+    extern fn store_nsevent(this: &mut Object, _sel: Sel, nsevent: id) {
         let event = {
             unsafe {
                 let event_type = nsevent.eventType();
@@ -38,32 +96,59 @@ pub fn register() {
                     NSScrollWheel => {
                         // Stolen from winit
                         use self::MouseScrollDelta::{LineDelta, PixelDelta};
-                        let nswindow: id = nsevent.window();
-                        let hidpi_factor: CGFloat = msg_send![nswindow, backingScaleFactor];
-                        let hidpi_factor = hidpi_factor as f32;
+                        let (scale_x, scale_y) = get_device_scale(this);
+                        let mut dx = scale_x * nsevent.scrollingDeltaX() as f32;
+                        let mut dy = scale_y * nsevent.scrollingDeltaY() as f32;
+                        // Depending on the device, Cocoa may or may not already
+                        // have turned a Shift+wheel into a horizontal scroll.
+                        let shift_scroll: BOOL = *this.get_ivar("shift_scroll_horizontal");
+                        let shift_pressed = nsevent.modifierFlags().contains(NSShiftKeyMask);
+                        if shift_scroll == YES && shift_pressed && dx == 0.0 && dy != 0.0 {
+                            dx = dy;
+                            dy = 0.0;
+                        }
+                        // Inertial scrolling is what makes the page bounce.
+                        // Drop it on the axis where elasticity is disabled.
+                        // Not only past the edges: that would need Servo to
+                        // tell us when it overscrolls.
+                        let momentum_phase: NSUInteger = msg_send![nsevent, momentumPhase];
+                        if momentum_phase != 0 {
+                            let horizontal: NSInteger = *this.get_ivar("horizontal_elasticity");
+                            let vertical: NSInteger = *this.get_ivar("vertical_elasticity");
+                            if horizontal == ELASTICITY_NONE { dx = 0.0; }
+                            if vertical == ELASTICITY_NONE { dy = 0.0; }
+                        }
                         let delta = if nsevent.hasPreciseScrollingDeltas() == YES {
-                            PixelDelta(hidpi_factor * nsevent.scrollingDeltaX() as f32,
-                            hidpi_factor * nsevent.scrollingDeltaY() as f32)
+                            PixelDelta(dx, dy)
                         } else {
-                            LineDelta(hidpi_factor * nsevent.scrollingDeltaX() as f32,
-                            hidpi_factor * nsevent.scrollingDeltaY() as f32)
+                            LineDelta(dx, dy)
                         };
                         let phase = match nsevent.phase() {
                             appkit::NSEventPhaseMayBegin | appkit::NSEventPhaseBegan => TouchPhase::Started,
                             appkit::NSEventPhaseEnded => TouchPhase::Ended,
                             _ => TouchPhase::Moved,
                         };
+                        let momentum_active: BOOL = *this.get_ivar("momentum_active");
+                        let interrupted = match phase {
+                            TouchPhase::Started => momentum_active == YES,
+                            _ => false,
+                        };
+                        if momentum_phase == MOMENTUM_PHASE_CANCELLED || interrupted {
+                            this.set_ivar("momentum_active", NO);
+                            let cancel = ViewEvent::MouseWheel(PixelDelta(0.0, 0.0), TouchPhase::Cancelled);
+                            utils::get_event_queue(this).push(cancel);
+                            if !interrupted {
+                                return;
+                            }
+                        } else if momentum_phase == MOMENTUM_PHASE_BEGAN {
+                            this.set_ivar("momentum_active", YES);
+                        } else if momentum_phase == MOMENTUM_PHASE_ENDED {
+                            this.set_ivar("momentum_active", NO);
+                        }
                         Some(ViewEvent::MouseWheel(delta, phase))
                     },
-                    NSMouseMoved => {
-                        let nswindow = nsevent.window();
-                        let window_point = nsevent.locationInWindow();
-                        let view_point: NSPoint = msg_send![this, convertPoint:window_point fromView:nil];
-                        let frame: NSRect = msg_send![this, frame];
-                        let hidpi_factor: CGFloat = msg_send![nswindow, backingScaleFactor];
-                        let hidpi_factor = hidpi_factor as f32;
-                        let x = (hidpi_factor * view_point.x as f32) as i32;
-                        let y = (hidpi_factor * (frame.size.height - view_point.y) as f32) as i32;
+                    NSMouseMoved | NSLeftMouseDragged => {
+                        let (x, y) = get_event_location(this, nsevent);
                         Some(ViewEvent::MouseMoved(x, y))
                     }
 
@@ -81,23 +166,374 @@ pub fn register() {
         };
 
         if let Some(event) = event {
+            let pressed = match event {
+                ViewEvent::MouseInput(ElementState::Pressed, button) => Some(button),
+                _ => None,
+            };
             utils::get_event_queue(this).push(event);
+            if let Some(button) = pressed {
+                let count = get_click_count(this, nsevent, button);
+                utils::get_event_queue(this).push(ViewEvent::ClickCount(button, count));
+            }
+        }
+    }
+
+    extern fn mouse_dragged(this: &mut Object, sel: Sel, nsevent: id) {
+        store_nsevent(this, sel, nsevent);
+        unsafe {
+            let enabled: BOOL = *this.get_ivar("autoscroll_enabled");
+            let autoscrolling: BOOL = *this.get_ivar("autoscrolling");
+            if enabled == NO {
+                return;
+            }
+            let (dx, dy) = get_autoscroll_delta(this);
+            let in_edge_zone = dx != 0.0 || dy != 0.0;
+            if in_edge_zone && autoscrolling == NO {
+                msg_send![class("NSEvent"), startPeriodicEventsAfterDelay:AUTOSCROLL_DELAY
+                                                               withPeriod:AUTOSCROLL_PERIOD];
+                this.set_ivar("autoscrolling", YES);
+            } else if !in_edge_zone && autoscrolling == YES {
+                stop_autoscroll(this);
+            }
+        }
+    }
+
+    extern fn mouse_up(this: &mut Object, sel: Sel, nsevent: id) {
+        stop_autoscroll(this);
+        store_nsevent(this, sel, nsevent);
+    }
+
+    // NSPeriodic events are not dispatched by NSApp. The run loop forwards
+    // them to the first responder of the key window.
+    extern fn periodic_event(this: &Object, _sel: Sel, _nsevent: id) {
+        let autoscrolling: BOOL = unsafe { *this.get_ivar("autoscrolling") };
+        if autoscrolling == NO {
+            return;
+        }
+        let (dx, dy) = get_autoscroll_delta(this);
+        if dx != 0.0 || dy != 0.0 {
+            utils::get_event_queue(this).push(ViewEvent::Autoscroll(dx, dy));
+        }
+    }
+
+    // Three finger tap, or force click, depending on the trackpad settings
+    extern fn quick_look(this: &Object, _sel: Sel, nsevent: id) {
+        let location = get_event_location(this, nsevent);
+        utils::get_event_queue(this).push(ViewEvent::QuickLook(location));
+    }
+
+    // On the main thread, from the display link or the fallback timer
+    extern fn display_link_fired(this: &Object, _sel: Sel) {
+        unsafe {
+            let ivar: *mut c_void = *this.get_ivar("frame_callback");
+            if !ivar.is_null() {
+                let callback: &Box<Fn()> = &*(ivar as *mut Box<Fn()>);
+                callback();
+            }
         }
     }
 
+    extern fn display_link_fired_by_timer(this: &Object, sel: Sel, _timer: id) {
+        display_link_fired(this, sel);
+    }
+
     extern fn awake_from_nib(this: &mut Object, _sel: Sel) {
         // FIXME: is that the best way to create a raw pointer?
         let event_queue: Vec<ViewEvent> = Vec::new();
         let event_queue_ptr = Box::into_raw(Box::new(event_queue));
         unsafe {
             this.set_ivar("event_queue", event_queue_ptr as *mut c_void);
+            this.set_ivar("shift_scroll_horizontal", YES);
+            this.set_ivar("opaque", YES);
+            this.set_ivar("suppress_beep", YES);
+            let mut click_counter = ClickCounter::new();
+            let interval: NSTimeInterval = msg_send![class("NSEvent"), doubleClickInterval];
+            click_counter.set_interval(Duration::from_millis((interval * 1000.0) as u64));
+            this.set_ivar("click_counter", Box::into_raw(Box::new(click_counter)) as *mut c_void);
+            this.set_ivar::<NSUInteger>("drag_operation", 0);
+            let types = vec![
+                NSString::alloc(nil).init_str("public.url"),
+                NSString::alloc(nil).init_str("public.file-url"),
+                NSString::alloc(nil).init_str("public.utf8-plain-text"),
+            ];
+            let types = NSArray::arrayWithObjects(nil, &types);
+            msg_send![this, registerForDraggedTypes:types];
+            let menu = build_context_menu(this as *mut Object as id);
+            msg_send![this, setMenu:menu];
+        }
+    }
+
+    extern fn dragging_updated(this: &Object, _sel: Sel, _info: id) -> NSUInteger {
+        let operation: NSUInteger = unsafe { *this.get_ivar("drag_operation") };
+        set_cursor_for_drag_operation(operation);
+        operation
+    }
+
+    extern fn dragging_ended(this: &mut Object, _sel: Sel, _info: id) {
+        unsafe {
+            this.set_ivar::<NSUInteger>("drag_operation", 0);
+        }
+        set_cursor("arrowCursor");
+    }
+
+    extern fn perform_drag_operation(_this: &Object, _sel: Sel, _info: id) -> BOOL {
+        // FIXME: drops are not forwarded to Servo yet
+        NO
+    }
+
+    extern fn will_start_live_resize(this: &Object, _sel: Sel) {
+        unsafe {
+            msg_send![super(this, Class::get("NSView").unwrap()), viewWillStartLiveResize];
+        }
+        utils::get_event_queue(this).push(ViewEvent::LiveResizeStarted);
+    }
+
+    extern fn did_end_live_resize(this: &Object, _sel: Sel) {
+        unsafe {
+            msg_send![super(this, Class::get("NSView").unwrap()), viewDidEndLiveResize];
+        }
+        utils::get_event_queue(this).push(ViewEvent::LiveResizeEnded);
+    }
+
+    extern fn key_down(this: &Object, _sel: Sel, nsevent: id) {
+        unsafe {
+            let events: id = msg_send![class("NSArray"), arrayWithObject:nsevent];
+            msg_send![this, interpretKeyEvents:events];
+        }
+    }
+
+    extern fn accessibility_children(this: &Object, _sel: Sel) -> id {
+        let nsview = this as *const Object as id;
+        if accessibility::get_provider(nsview).is_none() {
+            return unsafe { msg_send![super(this, Class::get("NSView").unwrap()), accessibilityChildren] };
+        }
+        accessibility::get_children(nsview, None)
+    }
+
+    extern fn accessibility_focused_element(this: &Object, _sel: Sel) -> id {
+        let nsview = this as *const Object as id;
+        match accessibility::get_provider(nsview) {
+            Some(provider) => match provider.focused() {
+                Some(node) => accessibility::get_element(nsview, node),
+                None => nsview,
+            },
+            None => unsafe { msg_send![super(this, Class::get("NSView").unwrap()), accessibilityFocusedUIElement] },
+        }
+    }
+
+    // Called by interpretKeyEvents: for keys bound to a command
+    // (moveUp:, insertNewline:…). Like NSResponder, try the whole responder
+    // chain (insertTab: for the window key view loop, cancelOperation:…),
+    // but only beep for unhandled commands if asked to.
+    extern fn do_command_by_selector(this: &Object, _sel: Sel, command: Sel) {
+        unsafe {
+            let mut handled: BOOL = msg_send![this, tryToPerform:command with:nil];
+            // NSWindow only walks the key view loop for Tab key events it
+            // gets itself
+            if handled == NO && (command == sel!(insertTab:) || command == sel!(insertBacktab:)) {
+                let nswindow: id = msg_send![this, window];
+                if command == sel!(insertTab:) {
+                    msg_send![nswindow, selectNextKeyView:this];
+                } else {
+                    msg_send![nswindow, selectPreviousKeyView:this];
+                }
+                handled = YES;
+            }
+            let suppress: BOOL = *this.get_ivar("suppress_beep");
+            if handled == NO && suppress == NO {
+                NSBeep();
+            }
+        }
+    }
+
+    extern fn flags_changed(this: &mut Object, _sel: Sel, nsevent: id) {
+        unsafe {
+            let flags = nsevent.modifierFlags();
+            let caps = flags.contains(NSAlphaShiftKeyMask);
+            let previous: BOOL = *this.get_ivar("caps_lock");
+            if caps != (previous == YES) {
+                this.set_ivar("caps_lock", if caps { YES } else { NO });
+                // No Num Lock / Scroll Lock modifier bits on macOS
+                let keys = LockKeys {
+                    caps: caps,
+                    num: false,
+                    scroll: false,
+                };
+                utils::get_event_queue(this).push(ViewEvent::LockKeysChanged(keys));
+            }
+        }
+    }
+
+    // Where the IME candidate window and the loupe go
+    extern fn first_rect_for_character_range(this: &Object, _sel: Sel, _range: NSRange, _actual_range: *mut NSRange) -> NSRect {
+        unsafe {
+            let pinned: BOOL = *this.get_ivar("ime_position_set");
+            if pinned == YES {
+                let x: i32 = *this.get_ivar("ime_position_x");
+                let y: i32 = *this.get_ivar("ime_position_y");
+                return get_rect_on_screen(this, (x, y, 0, 0));
+            }
+        }
+        get_first_selection_rect_on_screen(this)
+    }
+
+    extern fn accessibility_frame_for_range(this: &Object, _sel: Sel, _range: NSRange) -> NSRect {
+        get_first_selection_rect_on_screen(this)
+    }
+
+    /* NSTextInputClient */
+
+    extern fn insert_text(this: &mut Object, _sel: Sel, text: id) {
+        insert_text_replacement_range(this, sel!(insertText:replacementRange:), text, NSRange { location: NS_NOT_FOUND, length: 0 });
+    }
+
+    // Typed text, or the final text of a composition
+    extern fn insert_text_replacement_range(this: &mut Object, _sel: Sel, text: id, _range: NSRange) {
+        let was_composing = set_marked_text(this, nil);
+        let text = utils::nsstring_to_string(get_plain_string(text));
+        if was_composing {
+            utils::get_event_queue(this).push(ViewEvent::MarkedTextChanged(None));
+        }
+        if !text.is_empty() {
+            utils::get_event_queue(this).push(ViewEvent::TextInput(text));
+        }
+    }
+
+    extern fn set_marked_text_selected_range(this: &mut Object, _sel: Sel, text: id, _selected: NSRange, _replacement: NSRange) {
+        let text = get_plain_string(text);
+        let length: NSUInteger = unsafe { msg_send![text, length] };
+        if length == 0 {
+            if set_marked_text(this, nil) {
+                utils::get_event_queue(this).push(ViewEvent::MarkedTextChanged(None));
+            }
+            return;
+        }
+        set_marked_text(this, text);
+        let text = utils::nsstring_to_string(text);
+        utils::get_event_queue(this).push(ViewEvent::MarkedTextChanged(Some(text)));
+    }
+
+    // Commit the composition as it is
+    extern fn unmark_text(this: &mut Object, _sel: Sel) {
+        let marked: id = unsafe { *this.get_ivar("marked_text") };
+        if marked == nil {
+            return;
+        }
+        unsafe { msg_send![marked, retain] };
+        insert_text_replacement_range(this, sel!(insertText:replacementRange:), marked, NSRange { location: NS_NOT_FOUND, length: 0 });
+        unsafe {
+            msg_send![marked, release];
+            let context: id = msg_send![this, inputContext];
+            msg_send![context, discardMarkedText];
+        }
+    }
+
+    extern fn has_marked_text(this: &Object, _sel: Sel) -> BOOL {
+        let marked: id = unsafe { *this.get_ivar("marked_text") };
+        if marked == nil {NO} else {YES}
+    }
+
+    extern fn marked_range(this: &Object, _sel: Sel) -> NSRange {
+        let marked: id = unsafe { *this.get_ivar("marked_text") };
+        if marked == nil {
+            return NSRange { location: NS_NOT_FOUND, length: 0 };
+        }
+        let length: NSUInteger = unsafe { msg_send![marked, length] };
+        NSRange { location: 0, length: length }
+    }
+
+    // The page content isn't known here, so no ranges nor substrings
+    extern fn selected_range(_this: &Object, _sel: Sel) -> NSRange {
+        NSRange { location: NS_NOT_FOUND, length: 0 }
+    }
+
+    extern fn attributed_substring_for_proposed_range(_this: &Object, _sel: Sel, _range: NSRange, _actual: *mut NSRange) -> id {
+        nil
+    }
+
+    extern fn character_index_for_point(_this: &Object, _sel: Sel, _point: NSPoint) -> NSUInteger {
+        NS_NOT_FOUND
+    }
+
+    extern fn valid_attributes_for_marked_text(_this: &Object, _sel: Sel) -> id {
+        unsafe { msg_send![class("NSArray"), array] }
+    }
+
+    extern fn input_context(this: &Object, _sel: Sel) -> id {
+        unsafe {
+            let disabled: BOOL = *this.get_ivar("text_input_disabled");
+            if disabled == YES {
+                nil
+            } else {
+                msg_send![super(this, Class::get("NSView").unwrap()), inputContext]
+            }
         }
     }
 
+    // Cmd+X/C/V/A go through the main menu key equivalents, so menu items,
+    // keyboard shortcuts and context menus all end up here.
+    extern fn edit_action(this: &Object, sel: Sel, sender: id) {
+        let action = if sel == sel!(cut:) {
+            EditAction::Cut
+        } else if sel == sel!(copy:) {
+            EditAction::Copy
+        } else if sel == sel!(paste:) {
+            EditAction::Paste
+        } else {
+            EditAction::SelectAll
+        };
+        let source = unsafe {
+            let nsevent: id = msg_send![NSApp(), currentEvent];
+            let context_menu: id = msg_send![this, menu];
+            let is_key_event = nsevent != nil && nsevent.eventType() as u64 == NSKeyDown as u64;
+            let is_menu_item = utils::id_is_instance_of(sender, "NSMenuItem");
+            let sender_menu: id = if is_menu_item { msg_send![sender, menu] } else { nil };
+            if is_key_event {
+                ActionSource::Keyboard
+            } else if context_menu != nil && sender_menu == context_menu {
+                ActionSource::ContextMenu
+            } else {
+                ActionSource::Menu
+            }
+        };
+        utils::get_event_queue(this).push(ViewEvent::EditAction(action, source));
+    }
+
+    // A throttled redraw is due. get_events will queue it.
+    extern fn redraw_timer_fired(_this: &Object, _sel: Sel, _timer: id) {
+        app::wake_up();
+    }
+
     extern fn accept_first_responder(_this: &Object, _sel: Sel) -> BOOL {
         YES
     }
 
+    extern fn become_first_responder(this: &Object, _sel: Sel) -> BOOL {
+        unsafe {
+            let accepted: BOOL = msg_send![super(this, Class::get("NSView").unwrap()), becomeFirstResponder];
+            let wanted: BOOL = *this.get_ivar("secure_input");
+            let active: BOOL = msg_send![NSApp(), isActive];
+            if accepted == YES && wanted == YES && active == YES {
+                set_secure_event_input(true);
+            }
+            accepted
+        }
+    }
+
+    extern fn resign_first_responder(this: &Object, _sel: Sel) -> BOOL {
+        unsafe {
+            let accepted: BOOL = msg_send![super(this, Class::get("NSView").unwrap()), resignFirstResponder];
+            if accepted == YES {
+                set_secure_event_input(false);
+            }
+            accepted
+        }
+    }
+
+    extern fn is_opaque(this: &Object, _sel: Sel) -> BOOL {
+        unsafe { *this.get_ivar("opaque") }
+    }
+
     extern fn set_frame_size(this: &Object, _sel: Sel, size: NSSize) {
         unsafe {
             msg_send![super(this, Class::get("NSView").unwrap()), setFrameSize:size];
@@ -112,33 +548,388 @@ pub fn register() {
     }
 
     unsafe {
-        class.add_method(sel!(scrollWheel:), store_nsevent as extern fn(&Object, Sel, id));
-        class.add_method(sel!(mouseDown:), store_nsevent as extern fn(&Object, Sel, id));
-        class.add_method(sel!(mouseUp:), store_nsevent as extern fn(&Object, Sel, id));
-        class.add_method(sel!(mouseMoved:), store_nsevent as extern fn(&Object, Sel, id));
+        class.add_method(sel!(scrollWheel:), store_nsevent as extern fn(&mut Object, Sel, id));
+        class.add_method(sel!(mouseDown:), store_nsevent as extern fn(&mut Object, Sel, id));
+        class.add_method(sel!(mouseUp:), mouse_up as extern fn(&mut Object, Sel, id));
+        class.add_method(sel!(mouseMoved:), store_nsevent as extern fn(&mut Object, Sel, id));
+        class.add_method(sel!(mouseDragged:), mouse_dragged as extern fn(&mut Object, Sel, id));
+        class.add_method(sel!(shellPeriodicEvent:), periodic_event as extern fn(&Object, Sel, id));
+        class.add_method(sel!(quickLookWithEvent:), quick_look as extern fn(&Object, Sel, id));
+        class.add_method(sel!(shellDisplayLinkFired), display_link_fired as extern fn(&Object, Sel));
+        class.add_method(sel!(shellDisplayLinkFired:), display_link_fired_by_timer as extern fn(&Object, Sel, id));
+        class.add_method(sel!(shellRedrawTimerFired:), redraw_timer_fired as extern fn(&Object, Sel, id));
 
         class.add_method(sel!(acceptsFirstResponder), accept_first_responder as extern fn(&Object, Sel) -> BOOL);
+        class.add_method(sel!(becomeFirstResponder), become_first_responder as extern fn(&Object, Sel) -> BOOL);
+        class.add_method(sel!(resignFirstResponder), resign_first_responder as extern fn(&Object, Sel) -> BOOL);
+        class.add_method(sel!(isOpaque), is_opaque as extern fn(&Object, Sel) -> BOOL);
+
+        class.add_method(sel!(cut:), edit_action as extern fn(&Object, Sel, id));
+        class.add_method(sel!(copy:), edit_action as extern fn(&Object, Sel, id));
+        class.add_method(sel!(paste:), edit_action as extern fn(&Object, Sel, id));
+        class.add_method(sel!(selectAll:), edit_action as extern fn(&Object, Sel, id));
+
+        class.add_method(sel!(keyDown:), key_down as extern fn(&Object, Sel, id));
+        class.add_method(sel!(flagsChanged:), flags_changed as extern fn(&mut Object, Sel, id));
+        class.add_method(sel!(doCommandBySelector:), do_command_by_selector as extern fn(&Object, Sel, Sel));
+
+        class.add_method(sel!(accessibilityChildren), accessibility_children as extern fn(&Object, Sel) -> id);
+        class.add_method(sel!(accessibilityFocusedUIElement), accessibility_focused_element as extern fn(&Object, Sel) -> id);
+        class.add_method(sel!(insertText:), insert_text as extern fn(&mut Object, Sel, id));
+        class.add_method(sel!(insertText:replacementRange:),
+                         insert_text_replacement_range as extern fn(&mut Object, Sel, id, NSRange));
+        class.add_method(sel!(setMarkedText:selectedRange:replacementRange:),
+                         set_marked_text_selected_range as extern fn(&mut Object, Sel, id, NSRange, NSRange));
+        class.add_method(sel!(unmarkText), unmark_text as extern fn(&mut Object, Sel));
+        class.add_method(sel!(hasMarkedText), has_marked_text as extern fn(&Object, Sel) -> BOOL);
+        class.add_method(sel!(markedRange), marked_range as extern fn(&Object, Sel) -> NSRange);
+        class.add_method(sel!(selectedRange), selected_range as extern fn(&Object, Sel) -> NSRange);
+        class.add_method(sel!(attributedSubstringForProposedRange:actualRange:),
+                         attributed_substring_for_proposed_range as extern fn(&Object, Sel, NSRange, *mut NSRange) -> id);
+        class.add_method(sel!(characterIndexForPoint:), character_index_for_point as extern fn(&Object, Sel, NSPoint) -> NSUInteger);
+        class.add_method(sel!(validAttributesForMarkedText), valid_attributes_for_marked_text as extern fn(&Object, Sel) -> id);
+        class.add_method(sel!(inputContext), input_context as extern fn(&Object, Sel) -> id);
+        class.add_method(sel!(firstRectForCharacterRange:actualRange:),
+                         first_rect_for_character_range as extern fn(&Object, Sel, NSRange, *mut NSRange) -> NSRect);
+        class.add_method(sel!(accessibilityFrameForRange:), accessibility_frame_for_range as extern fn(&Object, Sel, NSRange) -> NSRect);
 
         class.add_method(sel!(setFrameSize:), set_frame_size as extern fn(&Object, Sel, NSSize));
+        class.add_method(sel!(viewWillStartLiveResize), will_start_live_resize as extern fn(&Object, Sel));
+        class.add_method(sel!(viewDidEndLiveResize), did_end_live_resize as extern fn(&Object, Sel));
 
         class.add_method(sel!(awakeFromNib), awake_from_nib as extern fn(&mut Object, Sel));
+
+        class.add_method(sel!(draggingEntered:), dragging_updated as extern fn(&Object, Sel, id) -> NSUInteger);
+        class.add_method(sel!(draggingUpdated:), dragging_updated as extern fn(&Object, Sel, id) -> NSUInteger);
+        class.add_method(sel!(draggingExited:), dragging_ended as extern fn(&mut Object, Sel, id));
+        class.add_method(sel!(concludeDragOperation:), dragging_ended as extern fn(&mut Object, Sel, id));
+        class.add_method(sel!(performDragOperation:), perform_drag_operation as extern fn(&Object, Sel, id) -> BOOL);
+    }
+
+    // Without it, NSView's inputContext is nil: no input methods, and
+    // set_text_input_type can't restrict the input sources.
+    match Protocol::get("NSTextInputClient") {
+        Some(protocol) => class.add_protocol(protocol),
+        None => warn!("NSTextInputClient protocol not found"),
     }
 
     class.register();
 }
 
+// Shown by NSView's menuForEvent: on right click / Ctrl+click. Its items
+// are told apart from the main menu ones in edit_action.
+unsafe fn build_context_menu(nsview: id) -> id {
+    let menu: id = msg_send![class("NSMenu"), new];
+    let items = [
+        ("Cut", sel!(cut:)),
+        ("Copy", sel!(copy:)),
+        ("Paste", sel!(paste:)),
+        ("Select All", sel!(selectAll:)),
+    ];
+    for &(title, action) in items.iter() {
+        let title = NSString::alloc(nil).init_str(title);
+        let key = NSString::alloc(nil).init_str("");
+        let item: id = msg_send![menu, addItemWithTitle:title action:action keyEquivalent:key];
+        msg_send![item, setTarget:nsview];
+    }
+    msg_send![menu, autorelease]
+}
+
+// NSNotFound
+const NS_NOT_FOUND: NSUInteger = ::std::i64::MAX as NSUInteger;
+
+// insertText: and setMarkedText: can get an NSAttributedString
+fn get_plain_string(text: id) -> id {
+    if utils::id_is_instance_of(text, "NSAttributedString") {
+        unsafe { msg_send![text, string] }
+    } else {
+        text
+    }
+}
+
+// Returns whether there was marked text before
+fn set_marked_text(nsview: &mut Object, text: id) -> bool {
+    unsafe {
+        let previous: id = *nsview.get_ivar("marked_text");
+        if text != nil {
+            msg_send![text, retain];
+        }
+        nsview.set_ivar("marked_text", text);
+        if previous != nil {
+            msg_send![previous, release];
+        }
+        previous != nil
+    }
+}
+
+#[link(name = "AppKit", kind = "framework")]
+extern {
+    fn NSAccessibilityPostNotification(element: id, notification: id);
+    fn NSBeep();
+}
+
+#[link(name = "Carbon", kind = "framework")]
+extern {
+    fn EnableSecureEventInput() -> i32;
+    fn DisableSecureEventInput() -> i32;
+}
+
+// Secure event input is a system wide counter. Make sure we never hold
+// more than one reference, and that we always give it back.
+static SECURE_INPUT_ENABLED: AtomicBool = ATOMIC_BOOL_INIT;
+
+pub fn set_secure_event_input(enabled: bool) {
+    if SECURE_INPUT_ENABLED.swap(enabled, Ordering::SeqCst) != enabled {
+        unsafe {
+            if enabled {
+                EnableSecureEventInput();
+            } else {
+                DisableSecureEventInput();
+            }
+        }
+    }
+}
+
+/// Secure event input is wanted if the app is active, and the focused
+/// view of the key window asked for it.
+pub fn sync_secure_event_input() {
+    unsafe {
+        let active: BOOL = msg_send![NSApp(), isActive];
+        let nswindow: id = msg_send![NSApp(), keyWindow];
+        let wanted = active == YES && nswindow != nil && {
+            let responder: id = msg_send![nswindow, firstResponder];
+            utils::id_is_instance_of(responder, "NSServoView") &&
+            *(&*responder).get_ivar::<BOOL>("secure_input") == YES
+        };
+        set_secure_event_input(wanted);
+    }
+}
+
+// Size of the area, in points, a positioned tooltip is attached to
+const TOOLTIP_AREA: CGFloat = 16.0;
+
+// NSEventPhase values, as found in momentumPhase
+const MOMENTUM_PHASE_BEGAN: NSUInteger = 1 << 0;
+const MOMENTUM_PHASE_ENDED: NSUInteger = 1 << 3;
+const MOMENTUM_PHASE_CANCELLED: NSUInteger = 1 << 4;
+
+// NSScrollElasticity values
+const ELASTICITY_AUTOMATIC: NSInteger = 0;
+const ELASTICITY_NONE: NSInteger = 1;
+const ELASTICITY_ALLOWED: NSInteger = 2;
+
+fn elasticity_to_nsscrollelasticity(elasticity: Elasticity) -> NSInteger {
+    match elasticity {
+        Elasticity::Automatic => ELASTICITY_AUTOMATIC,
+        Elasticity::None => ELASTICITY_NONE,
+        Elasticity::Allowed => ELASTICITY_ALLOWED,
+    }
+}
+
+// Framebuffer pixels per point, for both axis. That's the backing scale
+// factor, unless a fixed render size has been set.
+fn get_device_scale(nsview: &Object) -> (f32, f32) {
+    unsafe {
+        let width: u32 = *nsview.get_ivar("render_width");
+        let height: u32 = *nsview.get_ivar("render_height");
+        let frame: NSRect = msg_send![nsview, frame];
+        if width != 0 && height != 0 && frame.size.width > 0.0 && frame.size.height > 0.0 {
+            (width as f32 / frame.size.width as f32, height as f32 / frame.size.height as f32)
+        } else {
+            let nswindow: id = msg_send![nsview, window];
+            let hidpi_factor: CGFloat = msg_send![nswindow, backingScaleFactor];
+            (hidpi_factor as f32, hidpi_factor as f32)
+        }
+    }
+}
+
+// On the display link thread. The context is the NSServoView.
+extern fn display_link_output(_link: CVDisplayLinkRef, _now: *const c_void, _output_time: *const c_void,
+                              _flags_in: u64, _flags_out: *mut u64, context: *mut c_void) -> CVReturn {
+    unsafe { dispatch_async_f(dispatch_get_main_queue(), context, display_link_trampoline) };
+    K_CV_RETURN_SUCCESS
+}
+
+extern fn display_link_trampoline(context: *mut c_void) {
+    let nsview = context as id;
+    unsafe { msg_send![nsview, shellDisplayLinkFired] };
+}
+
+/// Queue a ModifiersChanged with the current state of the modifier keys
+/// into the NSServoView of this window, if any.
+pub fn report_modifiers(nswindow: id) {
+    if nswindow == nil {
+        return;
+    }
+    if let Some(nsview) = utils::get_view_by_id(nswindow, "shellViewServo") {
+        let flags: NSEventModifierFlags = unsafe { msg_send![class("NSEvent"), modifierFlags] };
+        let modifiers = Modifiers {
+            shift: flags.contains(NSShiftKeyMask),
+            ctrl: flags.contains(NSControlKeyMask),
+            alt: flags.contains(NSAlternateKeyMask),
+            cmd: flags.contains(NSCommandKeyMask),
+        };
+        utils::get_event_queue(unsafe { &*nsview }).push(ViewEvent::ModifiersChanged(modifiers));
+    }
+}
+
+// Synthetic events (CGEventPost, some remote desktop tools) can come
+// without a click count of their own.
+fn get_click_count(nsview: &Object, nsevent: id, button: MouseButton) -> u32 {
+    unsafe {
+        let count: NSInteger = msg_send![nsevent, clickCount];
+        if count > 0 {
+            return count as u32;
+        }
+        // No View yet, so no clock and nobody to count for
+        let clock: *mut c_void = *nsview.get_ivar("clock");
+        if clock.is_null() {
+            return 1;
+        }
+        let now = (*(clock as *mut Rc<Clock>)).now();
+        let point = get_event_location(nsview, nsevent);
+        utils::get_ivar::<ClickCounter>(nsview, "click_counter").press(now, point, button)
+    }
+}
+
+fn get_selection_rects<'a>(nsview: &'a Object) -> Option<&'a mut Vec<(i32, i32, u32, u32)>> {
+    unsafe {
+        let ivar: *mut c_void = *nsview.get_ivar("selection_rects");
+        if ivar.is_null() {
+            None
+        } else {
+            Some(&mut *(ivar as *mut Vec<(i32, i32, u32, u32)>))
+        }
+    }
+}
+
+// In screen coordinates. Without a selection, an empty rect at the top
+// left of the view.
+fn get_first_selection_rect_on_screen(nsview: &Object) -> NSRect {
+    let rect = get_selection_rects(nsview)
+        .and_then(|rects| rects.first().cloned())
+        .unwrap_or((0, 0, 0, 0));
+    get_rect_on_screen(nsview, rect)
+}
+
+// From points from the top left of the view
+fn get_rect_on_screen(nsview: &Object, (x, y, width, height): (i32, i32, u32, u32)) -> NSRect {
+    unsafe {
+        let frame: NSRect = msg_send![nsview, frame];
+        let rect = NSRect::new(NSPoint::new(x as CGFloat, frame.size.height - (y as CGFloat + height as CGFloat)),
+                               NSSize::new(width as CGFloat, height as CGFloat));
+        let in_window: NSRect = msg_send![nsview, convertRect:rect toView:nil];
+        let nswindow: id = msg_send![nsview, window];
+        msg_send![nswindow, convertRectToScreen:in_window]
+    }
+}
+
+// Location of a mouse event, in device pixels, from the top-left of the view
+fn get_event_location(nsview: &Object, nsevent: id) -> (i32, i32) {
+    unsafe {
+        let window_point = nsevent.locationInWindow();
+        let view_point: NSPoint = msg_send![nsview, convertPoint:window_point fromView:nil];
+        let frame: NSRect = msg_send![nsview, frame];
+        let (scale_x, scale_y) = get_device_scale(nsview);
+        let x = (scale_x * view_point.x as f32) as i32;
+        let y = (scale_y * (frame.size.height - view_point.y) as f32) as i32;
+        (x, y)
+    }
+}
+
+// How far the pointer is into the edge zone, in device pixels
+fn get_autoscroll_delta(nsview: &Object) -> (f32, f32) {
+    unsafe {
+        let nswindow: id = msg_send![nsview, window];
+        let window_point: NSPoint = msg_send![nswindow, mouseLocationOutsideOfEventStream];
+        let point: NSPoint = msg_send![nsview, convertPoint:window_point fromView:nil];
+        let frame: NSRect = msg_send![nsview, frame];
+        let (width, height) = (frame.size.width, frame.size.height);
+        // Cocoa coordinates: y goes up
+        let dx = if point.x < AUTOSCROLL_EDGE {
+            point.x - AUTOSCROLL_EDGE
+        } else if point.x > width - AUTOSCROLL_EDGE {
+            point.x - (width - AUTOSCROLL_EDGE)
+        } else {
+            0.0
+        };
+        let dy = if point.y < AUTOSCROLL_EDGE {
+            AUTOSCROLL_EDGE - point.y
+        } else if point.y > height - AUTOSCROLL_EDGE {
+            (height - AUTOSCROLL_EDGE) - point.y
+        } else {
+            0.0
+        };
+        let (scale_x, scale_y) = get_device_scale(nsview);
+        (scale_x * dx as f32, scale_y * dy as f32)
+    }
+}
+
+fn stop_autoscroll(nsview: &mut Object) {
+    unsafe {
+        let autoscrolling: BOOL = *nsview.get_ivar("autoscrolling");
+        if autoscrolling == YES {
+            msg_send![class("NSEvent"), stopPeriodicEvents];
+            nsview.set_ivar("autoscrolling", NO);
+        }
+    }
+}
+
+fn drag_operation_to_mask(operation: DragOperation) -> NSUInteger {
+    match operation {
+        DragOperation::None => 0,
+        DragOperation::Copy => 1,
+        DragOperation::Link => 2,
+        DragOperation::Move => 16,
+    }
+}
+
+fn set_cursor_for_drag_operation(operation: NSUInteger) {
+    let name = match operation {
+        1 => "dragCopyCursor",
+        2 => "dragLinkCursor",
+        16 => "arrowCursor",
+        _ => "operationNotAllowedCursor",
+    };
+    set_cursor(name);
+}
+
+fn set_cursor(name: &str) {
+    unsafe {
+        use objc::Message;
+        let cls = Class::get("NSCursor").unwrap();
+        let cursor: id = cls.send_message(Sel::register(name), ()).unwrap();
+        let _: () = msg_send![cursor, set];
+    }
+}
+
 pub struct View {
     nsview: id,
     context: id,
+    // NSImageView showing the last frame while the content is frozen
+    frozen_content: Cell<id>,
+    // None: fully opaque
+    opaque_region: RefCell<Option<Vec<(i32, i32, u32, u32)>>>,
+    clock: Rc<Clock>,
+    redraw_throttle: RefCell<RedrawThrottle>,
 }
 
 impl View {
 
-    pub fn new(nsview: id) -> View {
+    /// `clock` is the one from `AppOptions`.
+    pub fn new(nsview: id, clock: Rc<Clock>) -> View {
         let context: id = View::init_gl(nsview);
+        unsafe {
+            let clock_ptr = Box::into_raw(Box::new(clock.clone()));
+            (*nsview).set_ivar("clock", clock_ptr as *mut c_void);
+        }
         View {
             nsview: nsview,
-            context: context
+            context: context,
+            frozen_content: Cell::new(nil),
+            opaque_region: RefCell::new(None),
+            clock: clock,
+            redraw_throttle: RefCell::new(RedrawThrottle::new()),
         }
     }
 
@@ -150,10 +941,396 @@ impl View {
         }
     }
 
+    /// When enabled (the default), a vertical wheel scroll with Shift held
+    /// is reported as a horizontal one.
+    pub fn set_shift_scroll_horizontal(&self, enabled: bool) {
+        unsafe {
+            (*self.nsview).set_ivar("shift_scroll_horizontal", if enabled {YES} else {NO});
+        }
+    }
+
+    /// Show `text` as a native tooltip once the pointer hovers the view for
+    /// the system tooltip delay. With `at` (device pixels from the top left),
+    /// only around that point, typically where the hovered element is.
+    /// `None` hides it.
+    pub fn set_tooltip(&self, text: Option<&str>, at: Option<(i32, i32)>) {
+        unsafe {
+            msg_send![self.nsview, removeAllToolTips];
+            msg_send![self.nsview, setToolTip:nil];
+            // addToolTipRect: doesn't retain its owner
+            let previous: id = *(&*self.nsview).get_ivar("tooltip");
+            if previous != nil {
+                msg_send![previous, release];
+                (*self.nsview).set_ivar("tooltip", nil);
+            }
+            let text = match text {
+                Some(text) => NSString::alloc(nil).init_str(text),
+                None => return,
+            };
+            match at {
+                Some((x, y)) => {
+                    let (scale_x, scale_y) = get_device_scale(&*self.nsview);
+                    let frame: NSRect = msg_send![self.nsview, frame];
+                    let x = x as f64 / scale_x as f64;
+                    let y = frame.size.height - y as f64 / scale_y as f64;
+                    let rect = NSRect::new(NSPoint::new(x - TOOLTIP_AREA / 2.0, y - TOOLTIP_AREA / 2.0),
+                                           NSSize::new(TOOLTIP_AREA, TOOLTIP_AREA));
+                    // An NSString owner is used as the tooltip text
+                    msg_send![self.nsview, addToolTipRect:rect owner:text userData:ptr::null_mut::<c_void>()];
+                    (*self.nsview).set_ivar("tooltip", text);
+                }
+                None => {
+                    msg_send![self.nsview, setToolTip:text];
+                    msg_send![text, release];
+                }
+            }
+        }
+    }
+
+    /// Expose the embedder's accessibility tree (Servo's) to VoiceOver.
+    /// Replaces the previous provider.
+    pub fn set_accessibility_provider(&self, provider: Box<AccessibilityProvider>) {
+        unsafe {
+            let previous: *mut c_void = *(&*self.nsview).get_ivar("accessibility_provider");
+            let ptr = Box::into_raw(Box::new(provider));
+            (*self.nsview).set_ivar("accessibility_provider", ptr as *mut c_void);
+            if !previous.is_null() {
+                drop(Box::from_raw(previous as *mut Box<AccessibilityProvider>));
+            }
+            NSAccessibilityPostNotification(self.nsview, NSString::alloc(nil).init_str("AXLayoutChanged").autorelease());
+        }
+    }
+
+    /// Call when `AccessibilityProvider::focused` changes.
+    pub fn accessibility_focus_did_change(&self) {
+        unsafe {
+            let element: id = msg_send![self.nsview, accessibilityFocusedUIElement];
+            NSAccessibilityPostNotification(element, NSString::alloc(nil).init_str("AXFocusedUIElementChanged").autorelease());
+        }
+    }
+
+    /// Turn secure event input on while this view has the focus, for
+    /// password fields: other processes can't see the keystrokes. It's
+    /// turned off whenever the view loses the focus or the app is
+    /// deactivated, and back on when they come back.
+    pub fn set_secure_input(&self, enabled: bool) {
+        unsafe {
+            (*self.nsview).set_ivar("secure_input", if enabled {YES} else {NO});
+        }
+        sync_secure_event_input();
+    }
+
+    /// Don't beep on keys bound to a command nobody in the responder chain
+    /// implements, like arrow keys on a page that can't scroll. Suppressed
+    /// by default.
+    pub fn set_suppress_beep(&self, suppress: bool) {
+        unsafe {
+            (*self.nsview).set_ivar("suppress_beep", if suppress {YES} else {NO});
+        }
+    }
+
+    /// Operation to perform if the content being dragged over the view is
+    /// dropped. Updates the cursor, and is reported to the drag source on
+    /// the next draggingUpdated:. Reset once the drag exits or ends.
+    pub fn set_drag_cursor(&self, operation: DragOperation) {
+        let operation = drag_operation_to_mask(operation);
+        unsafe {
+            (*self.nsview).set_ivar("drag_operation", operation);
+        }
+        set_cursor_for_drag_operation(operation);
+    }
+
+    /// Clicks closer in time than that count as one double (triple…) click.
+    /// The system setting by default. Only used for events that come
+    /// without a click count.
+    pub fn set_double_click_interval(&self, interval: Duration) {
+        utils::get_ivar::<ClickCounter>(unsafe { &*self.nsview }, "click_counter").set_interval(interval);
+    }
+
+    /// Rects of the current text selection (or of the caret), in points from
+    /// the top left of the view. The first one places the IME candidate
+    /// window and the loupe. Empty for no selection.
+    pub fn set_selection_rects(&self, rects: &[(i32, i32, u32, u32)]) {
+        let nsobject = unsafe { &mut *self.nsview };
+        match get_selection_rects(nsobject) {
+            Some(current) => *current = rects.to_vec(),
+            None => unsafe {
+                let ptr = Box::into_raw(Box::new(rects.to_vec()));
+                nsobject.set_ivar("selection_rects", ptr as *mut c_void);
+            },
+        }
+    }
+
+    /// Pin the IME candidate window at this point (in points from the top
+    /// left of the view), instead of following the selection. Takes
+    /// precedence over `set_selection_rects`. `None` follows the selection
+    /// again.
+    pub fn set_ime_candidate_position(&self, position: Option<(i32, i32)>) {
+        unsafe {
+            let nsobject = &mut *self.nsview;
+            match position {
+                Some((x, y)) => {
+                    nsobject.set_ivar("ime_position_x", x);
+                    nsobject.set_ivar("ime_position_y", y);
+                    nsobject.set_ivar("ime_position_set", YES);
+                }
+                None => nsobject.set_ivar("ime_position_set", NO),
+            }
+        }
+    }
+
+    pub fn selection_rects(&self) -> Vec<(i32, i32, u32, u32)> {
+        let nsobject = unsafe { &*self.nsview };
+        get_selection_rects(nsobject).map(|rects| rects.clone()).unwrap_or(Vec::new())
+    }
+
+    /// Configure the input methods for the kind of field being edited.
+    /// Password, email and number fields only allow roman input sources, which
+    /// also means no IME candidates or marked text. None disables the
+    /// input context entirely. Normal and Search allow everything.
+    pub fn set_text_input_type(&self, kind: TextInputType) {
+        unsafe {
+            let disabled = if kind == TextInputType::None {YES} else {NO};
+            (*self.nsview).set_ivar("text_input_disabled", disabled);
+
+            let context: id = msg_send![self.nsview, inputContext];
+            if context == nil {
+                return;
+            }
+            msg_send![context, discardMarkedText];
+            let locales: id = match kind {
+                TextInputType::Password |
+                TextInputType::Email |
+                TextInputType::Number => {
+                    let roman = NSString::alloc(nil).init_str("NSAllRomanInputSourcesLocaleIdentifier");
+                    msg_send![class("NSArray"), arrayWithObject:roman]
+                }
+                TextInputType::Normal |
+                TextInputType::Search |
+                TextInputType::None => nil,
+            };
+            msg_send![context, setAllowedInputSourceLocales:locales];
+        }
+    }
+
+    /// Render at a fixed framebuffer size, whatever the size of the view is.
+    /// The window server stretches the GL surface to the view bounds, with
+    /// linear filtering (no aspect ratio preserved). Pass 0x0 to go back to
+    /// the native resolution of the view.
+    pub fn set_render_size(&self, width: u32, height: u32) {
+        unsafe {
+            (*self.nsview).set_ivar("render_width", width);
+            (*self.nsview).set_ivar("render_height", height);
+            let cgl_context = self.context.CGLContextObj() as *mut _;
+            if width == 0 || height == 0 {
+                CGLDisable(cgl_context, K_CGL_CE_SURFACE_BACKING_SIZE);
+            } else {
+                let size = [width as i32, height as i32];
+                CGLSetParameter(cgl_context, K_CGL_CP_SURFACE_BACKING_SIZE, size.as_ptr());
+                CGLEnable(cgl_context, K_CGL_CE_SURFACE_BACKING_SIZE);
+            }
+            msg_send![self.context, update];
+            utils::get_event_queue(&*self.nsview).push(ViewEvent::GeometryDidChange);
+        }
+    }
+
+    /// Move the servo view into `parent`, an NSView of the embedder (usually
+    /// in another window than the one it was loaded in), filling it.
+    ///
+    /// `parent` retains the servo view from then on, and must outlive this
+    /// View. The GL context follows the servo view. The window of origin
+    /// doesn't need to stay around, and doesn't receive the view events
+    /// anymore (they are still queued in this View).
+    pub fn attach_to(&self, parent: id) {
+        self.thaw_content();
+        unsafe {
+            let nsview = self.nsview;
+            // Don't let the view be deallocated in between
+            msg_send![nsview, retain];
+            msg_send![nsview, removeFromSuperview];
+            msg_send![parent, addSubview:nsview];
+            msg_send![nsview, release];
+
+            // The nib constraints are gone with the old superview
+            let bounds: NSRect = msg_send![parent, bounds];
+            msg_send![nsview, setTranslatesAutoresizingMaskIntoConstraints:YES];
+            msg_send![nsview, setFrame:bounds];
+            msg_send![nsview, setAutoresizingMask:18 as NSUInteger]; // NSViewWidthSizable | NSViewHeightSizable
+
+            // The new window might not accept mouse moved events. A tracking
+            // area works in any window. InVisibleRect keeps it up to date
+            // as the view is resized.
+            let areas: id = msg_send![nsview, trackingAreas];
+            let count: NSInteger = msg_send![areas, count];
+            for i in (0..count).rev() {
+                let area: id = msg_send![areas, objectAtIndex:i];
+                let owner: id = msg_send![area, owner];
+                if owner == nsview {
+                    msg_send![nsview, removeTrackingArea:area];
+                }
+            }
+            // NSTrackingMouseMoved | NSTrackingActiveInKeyWindow | NSTrackingInVisibleRect
+            let options: NSUInteger = 0x02 | 0x20 | 0x200;
+            let area: id = msg_send![class("NSTrackingArea"), alloc];
+            let area: id = msg_send![area, initWithRect:NSRect::new(NSPoint::new(0., 0.), NSSize::new(0., 0.))
+                                                options:options
+                                                  owner:nsview
+                                               userInfo:nil];
+            msg_send![nsview, addTrackingArea:area];
+            msg_send![area, release];
+
+            msg_send![self.context, setView:nsview];
+            msg_send![self.context, update];
+            utils::get_event_queue(&*nsview).push(ViewEvent::GeometryDidChange);
+        }
+    }
+
+    /// Call `callback` on the main thread once per display refresh, driven by
+    /// a CVDisplayLink. If it can't be created (some virtual machines), falls
+    /// back to a timer at the refresh rate of the screen, and sends
+    /// DisplayLinkFallback once. Replaces any previous callback.
+    pub fn start_display_link(&self, callback: Box<Fn()>) {
+        self.stop_display_link();
+        let callback_ptr = Box::into_raw(Box::new(callback));
+        unsafe {
+            (*self.nsview).set_ivar("frame_callback", callback_ptr as *mut c_void);
+
+            let mut link: CVDisplayLinkRef = ptr::null_mut();
+            let mut ok = CVDisplayLinkCreateWithActiveCGDisplays(&mut link) == K_CV_RETURN_SUCCESS;
+            if ok {
+                CVDisplayLinkSetOutputCallback(link, display_link_output, self.nsview as *mut c_void);
+                ok = CVDisplayLinkStart(link) == K_CV_RETURN_SUCCESS;
+                if !ok {
+                    CVDisplayLinkRelease(link);
+                }
+            }
+            if ok {
+                (*self.nsview).set_ivar("display_link", link);
+                return;
+            }
+
+            let nswindow: id = msg_send![self.nsview, window];
+            let nsscreen: id = msg_send![nswindow, screen];
+            let rate = if nsscreen == nil {
+                Screen::main().refresh_rate()
+            } else {
+                Screen::new(nsscreen).refresh_rate()
+            };
+            warn!("No display link available, falling back to a {}Hz timer", rate);
+            let timer: id = msg_send![class("NSTimer"), scheduledTimerWithTimeInterval:1.0 / rate as f64
+                                                                               target:self.nsview
+                                                                             selector:sel!(shellDisplayLinkFired:)
+                                                                             userInfo:nil
+                                                                              repeats:YES];
+            msg_send![timer, retain];
+            (*self.nsview).set_ivar("display_timer", timer);
+
+            let reported: BOOL = *(&*self.nsview).get_ivar("display_link_fallback_reported");
+            if reported == NO {
+                (*self.nsview).set_ivar("display_link_fallback_reported", YES);
+                utils::get_event_queue(&*self.nsview).push(ViewEvent::DisplayLinkFallback);
+            }
+        }
+    }
+
+    pub fn stop_display_link(&self) {
+        unsafe {
+            let link: *mut c_void = *(&*self.nsview).get_ivar("display_link");
+            if !link.is_null() {
+                // Waits for a running output callback to return
+                CVDisplayLinkStop(link);
+                CVDisplayLinkRelease(link);
+                (*self.nsview).set_ivar("display_link", ptr::null_mut::<c_void>());
+            }
+            let timer: id = *(&*self.nsview).get_ivar("display_timer");
+            if timer != nil {
+                msg_send![timer, invalidate];
+                msg_send![timer, release];
+                (*self.nsview).set_ivar("display_timer", nil);
+            }
+            // Already queued main thread calls see a null callback and do nothing
+            let callback: *mut c_void = *(&*self.nsview).get_ivar("frame_callback");
+            if !callback.is_null() {
+                (*self.nsview).set_ivar("frame_callback", ptr::null_mut::<c_void>());
+                drop(Box::from_raw(callback as *mut Box<Fn()>));
+            }
+        }
+    }
+
+    /// Parts of the view (x, y, width, height, in device pixels from the top
+    /// left) where the content is opaque. Anything else is blended with
+    /// what's below the view (vibrancy). An empty slice means fully
+    /// transparent. By default, the view is fully opaque.
+    ///
+    /// The GL surface can only be opaque as a whole: it stays opaque only if
+    /// one of the rects covers the entire view. Otherwise, it's made
+    /// transparent, and Servo's output alpha is used everywhere.
+    pub fn set_opaque_region(&self, rects: &[(i32, i32, u32, u32)]) {
+        let (width, height) = self.get_geometry().render_size;
+        let opaque = rects.iter().any(|&(x, y, w, h)| {
+            x <= 0 && y <= 0 &&
+            x + w as i32 >= width as i32 &&
+            y + h as i32 >= height as i32
+        });
+        *self.opaque_region.borrow_mut() = Some(rects.to_vec());
+        self.set_surface_opaque(opaque);
+    }
+
+    /// None if the view is fully opaque (the default).
+    pub fn opaque_region(&self) -> Option<Vec<(i32, i32, u32, u32)>> {
+        self.opaque_region.borrow().clone()
+    }
+
+    fn set_surface_opaque(&self, opaque: bool) {
+        unsafe {
+            let value = if opaque {1} else {0};
+            self.context.setValues_forParameter_(&value, NSOpenGLContextParameter::NSOpenGLCPSurfaceOpacity);
+            // Lets AppKit skip drawing what's below
+            (*self.nsview).set_ivar("opaque", if opaque {YES} else {NO});
+            let nswindow: id = msg_send![self.nsview, window];
+            msg_send![nswindow, invalidateShadow];
+        }
+    }
+
+    /// When enabled, dragging with the left button held close to the edges
+    /// of the view (like when selecting text) emits Autoscroll events until
+    /// the pointer leaves the edge zone or the button is released. Scrolling
+    /// and extending the selection is up to the embedder. Disabled by default.
+    pub fn set_autoscroll_enabled(&self, enabled: bool) {
+        unsafe {
+            (*self.nsview).set_ivar("autoscroll_enabled", if enabled {YES} else {NO});
+            if !enabled {
+                stop_autoscroll(&mut *self.nsview);
+            }
+        }
+    }
+
+    /// Control the rubber-band effect. If the view is embedded in a scroll
+    /// view, that's forwarded to it. Otherwise, this isn't real elasticity:
+    /// None drops the momentum (inertial) part of every scroll on that axis,
+    /// within the page too, so flicks stop when the fingers are lifted.
+    /// Allowed and Automatic then both leave the events untouched. Both
+    /// default to Automatic.
+    pub fn set_scroll_elasticity(&self, horizontal: Elasticity, vertical: Elasticity) {
+        let horizontal = elasticity_to_nsscrollelasticity(horizontal);
+        let vertical = elasticity_to_nsscrollelasticity(vertical);
+        unsafe {
+            (*self.nsview).set_ivar("horizontal_elasticity", horizontal);
+            (*self.nsview).set_ivar("vertical_elasticity", vertical);
+            let scrollview: id = msg_send![self.nsview, enclosingScrollView];
+            if scrollview != nil {
+                msg_send![scrollview, setHorizontalScrollElasticity:horizontal];
+                msg_send![scrollview, setVerticalScrollElasticity:vertical];
+            }
+        }
+    }
+
     pub fn swap_buffers(&self) {
+        self.paint_letterbox();
         unsafe {
             msg_send![self.context, flushBuffer];
         }
+        app::did_present();
     }
 
     pub fn update_drawable(&self) {
@@ -167,30 +1344,160 @@ impl View {
             let nswindow: id = msg_send![self.nsview, window];
             let content_view: id = msg_send![nswindow, contentView];
 
-            let hidpi_factor: CGFloat = msg_send![nswindow, backingScaleFactor];
+            let (scale_x, scale_y) = get_device_scale(&*self.nsview);
 
             let view_frame: NSRect = msg_send![self.nsview, frame];
             let content_frame: NSRect = msg_send![content_view, frame];
             let visible_rect: NSRect = msg_send![nswindow, contentLayoutRect];
 
-            let bottom = 0;
-            let top = (content_frame.size.height - visible_rect.size.height) as u32;
-            let left = 0;
-            let right = 0;
+            let (letterbox_top, letterbox_right, letterbox_bottom, letterbox_left) = self.get_letterbox();
+
+            let bottom = letterbox_bottom;
+            let top = (content_frame.size.height - visible_rect.size.height) as u32 + letterbox_top;
+            let left = letterbox_left;
+            let right = letterbox_right;
+
+            let render_width = (view_frame.size.width as f32 * scale_x).round() as u32;
+            let render_height = (view_frame.size.height as f32 * scale_y).round() as u32;
 
             DrawableGeometry {
                 view_size: (view_frame.size.width as u32, view_frame.size.height as u32),
+                render_size: (render_width, render_height),
                 margins: (top, right, bottom, left),
                 position: (0, 0),
-                hidpi_factor: hidpi_factor as f32,
+                hidpi_factor: scale_x,
             }
         }
     }
 
+    // Bars (top, right, bottom, left), in points, that keep the content area
+    // to the aspect ratio set with Window::set_fullscreen_aspect_ratio.
+    fn get_letterbox(&self) -> (u32, u32, u32, u32) {
+        unsafe {
+            let nswindow: id = msg_send![self.nsview, window];
+            if !utils::id_is_instance_of(nswindow, "NSShellWindow") {
+                return (0, 0, 0, 0);
+            }
+            let mask: NSUInteger = msg_send![nswindow, styleMask];
+            let fullscreen = mask & (1 << 14) != 0; // NSWindowStyleMaskFullScreen
+            let aspect_width: u32 = *(&*nswindow).get_ivar("fullscreen_aspect_width");
+            let aspect_height: u32 = *(&*nswindow).get_ivar("fullscreen_aspect_height");
+            if !fullscreen || aspect_width == 0 || aspect_height == 0 {
+                return (0, 0, 0, 0);
+            }
+
+            let content_view: id = msg_send![nswindow, contentView];
+            let content_frame: NSRect = msg_send![content_view, frame];
+            let visible_rect: NSRect = msg_send![nswindow, contentLayoutRect];
+            let view_frame: NSRect = msg_send![self.nsview, frame];
+            let toolbar = content_frame.size.height - visible_rect.size.height;
+            let width = view_frame.size.width;
+            let height = view_frame.size.height - toolbar;
+            let ratio = aspect_width as f64 / aspect_height as f64;
+            if width > height * ratio {
+                // Pillarbox
+                let extra = (width - height * ratio) as u32;
+                (0, extra - extra / 2, 0, extra / 2)
+            } else {
+                let extra = (height - width / ratio) as u32;
+                (extra / 2, 0, extra - extra / 2, 0)
+            }
+        }
+    }
+
+    // Servo doesn't draw outside of its rect. Fill the letterbox bars.
+    fn paint_letterbox(&self) {
+        let (top, right, bottom, left) = self.get_letterbox();
+        if (top, right, bottom, left) == (0, 0, 0, 0) {
+            return;
+        }
+        let geometry = self.get_geometry();
+        let (scale_x, scale_y) = geometry.render_scale();
+        let (width, height) = geometry.render_size;
+        let toolbar = geometry.margins.0 - top;
+        let (top, right, bottom, left) = ((top as f32 * scale_y) as u32, (right as f32 * scale_x) as u32,
+                                          (bottom as f32 * scale_y) as u32, (left as f32 * scale_x) as u32);
+        let toolbar = (toolbar as f32 * scale_y) as u32;
+        let (r, g, b, a) = unsafe {
+            let nswindow: id = msg_send![self.nsview, window];
+            let color: id = msg_send![nswindow, backgroundColor];
+            let space: id = msg_send![class("NSColorSpace"), sRGBColorSpace];
+            let color: id = msg_send![color, colorUsingColorSpace:space];
+            if color == nil {
+                (0.0, 0.0, 0.0, 1.0)
+            } else {
+                let r: CGFloat = msg_send![color, redComponent];
+                let g: CGFloat = msg_send![color, greenComponent];
+                let b: CGFloat = msg_send![color, blueComponent];
+                let a: CGFloat = msg_send![color, alphaComponent];
+                (r as f32, g as f32, b as f32, a as f32)
+            }
+        };
+        // GL coordinates: origin at the bottom left
+        let bars = [
+            (0, 0, left, height),
+            (width - right, 0, right, height),
+            (0, 0, width, bottom),
+            (0, height - toolbar - top, width, top),
+        ];
+        gleam::gl::clear_color(r, g, b, a);
+        gleam::gl::enable(gleam::gl::SCISSOR_TEST);
+        for &(x, y, w, h) in bars.iter() {
+            if w > 0 && h > 0 {
+                gleam::gl::scissor(x as i32, y as i32, w as i32, h as i32);
+                gleam::gl::clear(gleam::gl::COLOR_BUFFER_BIT);
+            }
+        }
+        gleam::gl::disable(gleam::gl::SCISSOR_TEST);
+    }
+
+    /// Queue a RedrawRequested, unless one has already been delivered less
+    /// than `min_render_interval` ago. In that case, all the requests made
+    /// within the interval are coalesced into one RedrawRequested, delivered
+    /// at the end of the interval.
+    pub fn request_redraw(&self) {
+        let action = self.redraw_throttle.borrow_mut().request(self.clock.now());
+        self.perform_redraw_action(action);
+    }
+
+    /// Never deliver RedrawRequested more often than that. A zero duration
+    /// disables throttling.
+    pub fn set_min_render_interval(&self, interval: Duration) {
+        let action = self.redraw_throttle.borrow_mut().set_interval(interval, self.clock.now());
+        self.perform_redraw_action(action);
+    }
+
+    // Deliver the coalesced redraw once its interval is over
+    fn flush_pending_redraw(&self) {
+        let action = self.redraw_throttle.borrow_mut().flush(self.clock.now());
+        self.perform_redraw_action(action);
+    }
+
+    fn perform_redraw_action(&self, action: RedrawAction) {
+        match action {
+            RedrawAction::Deliver => {
+                let nsobject = unsafe { &*self.nsview};
+                utils::get_event_queue(nsobject).push(ViewEvent::RedrawRequested);
+                // The run loop might be idle, blocked until the next input
+                app::note_activity();
+            }
+            RedrawAction::ScheduleIn(delay) => unsafe {
+                msg_send![class("NSTimer"), scheduledTimerWithTimeInterval:delay
+                                                                     target:self.nsview
+                                                                   selector:sel!(shellRedrawTimerFired:)
+                                                                   userInfo:nil
+                                                                    repeats:NO];
+            },
+            RedrawAction::Wait => {}
+        }
+    }
+
+    /// Ordered by priority, see `priority::Priority`.
     pub fn get_events(&self) -> Vec<ViewEvent> {
+        self.flush_pending_redraw();
         // FIXME: we should allow only one GeometryDidChange
         let nsobject = unsafe { &*self.nsview};
-        utils::get_event_queue(nsobject).drain(..).collect()
+        priority::sort_by_priority(utils::get_event_queue(nsobject).drain(..).collect())
     }
 
     pub fn enter_fullscreen(&self) {
@@ -205,6 +1512,118 @@ impl View {
         }
     }
 
+    /// Capture the current frame and display it, stretched to the view size,
+    /// until `thaw_content` is called. Meant to be used during live resize to
+    /// avoid re-rendering the page for every intermediate size.
+    pub fn freeze_content(&self) {
+        if self.is_content_frozen() {
+            return
+        }
+        unsafe {
+            let frame: NSRect = msg_send![self.nsview, frame];
+            let (width, height) = self.get_geometry().render_size;
+
+            // The back buffer is undefined once it has been flushed
+            gleam::gl::read_buffer(gleam::gl::FRONT);
+            let pixels = View::read_pixels(0, 0, width, height);
+            gleam::gl::read_buffer(gleam::gl::BACK);
+
+            let rep: id = msg_send![class("NSBitmapImageRep"), alloc];
+            let rep: id = msg_send![rep, initWithBitmapDataPlanes:0 as *mut *mut u8
+                                          pixelsWide:width as NSInteger
+                                          pixelsHigh:height as NSInteger
+                                          bitsPerSample:8 as NSInteger
+                                          samplesPerPixel:4 as NSInteger
+                                          hasAlpha:YES
+                                          isPlanar:NO
+                                          colorSpaceName:NSString::alloc(nil).init_str("NSDeviceRGBColorSpace")
+                                          bytesPerRow:(width * 4) as NSInteger
+                                          bitsPerPixel:32 as NSInteger];
+            let data: *mut u8 = msg_send![rep, bitmapData];
+            ::std::ptr::copy_nonoverlapping(pixels.as_ptr(), data, pixels.len());
+
+            let image: id = msg_send![class("NSImage"), alloc];
+            let image: id = msg_send![image, initWithSize:frame.size];
+            msg_send![image, addRepresentation:rep];
+            msg_send![rep, release];
+
+            let image_view: id = msg_send![class("NSImageView"), alloc];
+            let image_view: id = msg_send![image_view, initWithFrame:frame];
+            msg_send![image_view, setImage:image];
+            msg_send![image, release];
+            msg_send![image_view, setImageScaling:1 as NSUInteger]; // NSImageScaleAxesIndependently
+            msg_send![image_view, setAutoresizingMask:18 as NSUInteger]; // NSViewWidthSizable | NSViewHeightSizable
+
+            let superview: id = msg_send![self.nsview, superview];
+            msg_send![superview, addSubview:image_view positioned:1 relativeTo:self.nsview]; // NSWindowAbove
+            self.frozen_content.set(image_view);
+        }
+    }
+
+    /// The last presented frame, at the framebuffer size (`render_size`).
+    pub fn capture(&self) -> ImageData {
+        let (width, height) = self.get_geometry().render_size;
+        self.capture_region((0, 0, width, height))
+    }
+
+    /// Part of the last presented frame, for example to leave the margins
+    /// out. (x, y, width, height) in framebuffer pixels from the top left,
+    /// like `capture`. Clamped to the framebuffer.
+    pub fn capture_region(&self, rect: (i32, i32, u32, u32)) -> ImageData {
+        let (render_width, render_height) = self.get_geometry().render_size;
+        let (x, y, width, height) = rect;
+        let left = x.max(0).min(render_width as i32) as u32;
+        let top = y.max(0).min(render_height as i32) as u32;
+        let right = (x as i64 + width as i64).max(0).min(render_width as i64) as u32;
+        let bottom = (y as i64 + height as i64).max(0).min(render_height as i64) as u32;
+        if right <= left || bottom <= top {
+            return ImageData {
+                width: 0,
+                height: 0,
+                pixels: Vec::new(),
+            };
+        }
+        let (width, height) = (right - left, bottom - top);
+        // The back buffer is undefined once it has been flushed
+        gleam::gl::read_buffer(gleam::gl::FRONT);
+        let pixels = View::read_pixels(left, render_height - bottom, width, height);
+        gleam::gl::read_buffer(gleam::gl::BACK);
+        ImageData {
+            width: width,
+            height: height,
+            pixels: pixels,
+        }
+    }
+
+    /// Remove the frame captured by `freeze_content` and go back to live rendering.
+    pub fn thaw_content(&self) {
+        let image_view = self.frozen_content.get();
+        self.frozen_content.set(nil);
+        if image_view != nil {
+            unsafe {
+                msg_send![image_view, removeFromSuperview];
+                msg_send![image_view, release];
+            }
+        }
+    }
+
+    pub fn is_content_frozen(&self) -> bool {
+        self.frozen_content.get() != nil
+    }
+
+    // Read back a rectangle of the framebuffer (origin at the bottom left,
+    // like GL), and return RGBA rows ordered from top to bottom.
+    fn read_pixels(x: u32, y: u32, width: u32, height: u32) -> Vec<u8> {
+        let pixels = gleam::gl::read_pixels(x as i32, y as i32, width as i32, height as i32,
+                                            gleam::gl::RGBA, gleam::gl::UNSIGNED_BYTE);
+        let stride = (width * 4) as usize;
+        let mut flipped = Vec::with_capacity(pixels.len());
+        for row in pixels.chunks(stride).rev() {
+            flipped.extend_from_slice(row);
+        }
+        flipped
+    }
+
     fn init_gl(nsview: id) -> id {
         let ctx = unsafe {
             nsview.setWantsBestResolutionOpenGLSurface_(YES);
@@ -10,14 +10,52 @@ use objc::runtime::{Class, Object, Sel};
 use std::f64;
 use std::ffi::CStr;
 use std::os::raw::c_void;
+use std::ptr;
+use std::rc::Rc;
 use super::utils;
-use window::{WindowEvent, WindowCommand};
-use view::View;
+use priority;
+use window::{AnimationBehavior, TabbingMode, WindowEvent, WindowCommand, WindowId, WindowOptions, WindowStyle};
+use app::{AppEvent, Appearance, Shortcut};
+use window::{STYLE_TITLED, STYLE_CLOSABLE, STYLE_MINIATURIZABLE, STYLE_RESIZABLE, STYLE_FULL_SIZE_CONTENT};
+use view::{View, ViewEvent};
 use libc;
 use servo::ServoCursor;
 use state::WindowState;
 use super::get_state;
+use super::app;
 use super::logs::ShellLog;
+use super::controls::{self, Controls};
+use controls::ControlEvent;
+use super::screen::Screen;
+use super::view;
+use super::app::App;
+use error::ShellError;
+use clock::Clock;
+
+// kVK_Tab
+const KEY_CODE_TAB: u16 = 0x30;
+
+// NSApplicationPresentationAutoHideToolbar
+const PRESENTATION_AUTO_HIDE_TOOLBAR: NSUInteger = 1 << 11;
+
+// NSWindowCollectionBehaviorFullScreenNone
+const COLLECTION_BEHAVIOR_FULLSCREEN_NONE: NSUInteger = 1 << 9;
+
+// In points
+const DEFAULT_RESIZE_GRAB_MARGIN: CGFloat = 5.0;
+
+const EDGE_LEFT: u8 = 1 << 0;
+const EDGE_RIGHT: u8 = 1 << 1;
+const EDGE_BOTTOM: u8 = 1 << 2;
+const EDGE_TOP: u8 = 1 << 3;
+
+// An edge drag in progress
+struct ResizeDrag {
+    edges: u8,
+    start_frame: NSRect,
+    // Screen coordinates
+    start_mouse: NSPoint,
+}
 
 pub fn register() {
 
@@ -26,6 +64,52 @@ pub fn register() {
         let superclass = Class::get("NSWindow").unwrap();
         let mut class = ClassDecl::new("NSShellWindow", superclass).unwrap();
         class.add_ivar::<*mut c_void>("event_queue");
+        // Events of the Controls
+        class.add_ivar::<*mut c_void>("control_event_queue");
+        class.add_ivar::<BOOL>("fullscreen_toolbar_autohide");
+        // -1: NSWindow decides (no for borderless windows), 0: no, 1: yes
+        class.add_ivar::<i8>("can_become_key");
+        class.add_ivar::<i8>("can_become_main");
+        class.add_ivar::<BOOL>("close_blocked");
+        // 0x0: no aspect ratio constraint
+        class.add_ivar::<u32>("fullscreen_aspect_width");
+        class.add_ivar::<u32>("fullscreen_aspect_height");
+        // Of the screen the window was last on
+        class.add_ivar::<f32>("refresh_rate");
+        // Resize by dragging the edges, see set_resize_affordance
+        class.add_ivar::<BOOL>("resize_affordance");
+        class.add_ivar::<CGFloat>("resize_grab_margin");
+        class.add_ivar::<*mut c_void>("resize_drag");
+        // Box<Vec<(f32, f32, f32, f32)>>, see Controls::set_non_draggable_subregions
+        class.add_ivar::<*mut c_void>("non_draggable_subregions");
+        // The window was made unmovable for the current click
+        class.add_ivar::<BOOL>("movable_on_mouse_up");
+        // See set_vibrancy. NO, so vibrant, by default
+        class.add_ivar::<BOOL>("vibrancy_disabled");
+
+        extern fn send_event(this: &mut Object, _sel: Sel, nsevent: id) {
+            if handle_resize_affordance(this, nsevent) {
+                return;
+            }
+            unsafe {
+                let event_type = nsevent.eventType();
+                if let NSLeftMouseDown = event_type {
+                    let movable: BOOL = msg_send![this, isMovable];
+                    if movable == YES && controls::is_in_non_draggable_subregion(this, nsevent.locationInWindow()) {
+                        msg_send![this, setMovable:NO];
+                        this.set_ivar("movable_on_mouse_up", YES);
+                    }
+                }
+                msg_send![super(this, Class::get("NSWindow").unwrap()), sendEvent:nsevent];
+                if let NSLeftMouseUp = event_type {
+                    let restore: BOOL = *this.get_ivar("movable_on_mouse_up");
+                    if restore == YES {
+                        msg_send![this, setMovable:YES];
+                        this.set_ivar("movable_on_mouse_up", NO);
+                    }
+                }
+            }
+        }
 
         extern fn toggle_tabbar(this: &Object, _sel: Sel, sender: id) {
             unsafe {
@@ -45,12 +129,70 @@ pub fn register() {
             utils::get_event_queue(this).push(WindowEvent::EventLoopRised);
         }
 
+        extern fn can_become_key(this: &Object, _sel: Sel) -> BOOL {
+            unsafe {
+                match *this.get_ivar::<i8>("can_become_key") {
+                    0 => NO,
+                    1 => YES,
+                    _ => msg_send![super(this, Class::get("NSWindow").unwrap()), canBecomeKeyWindow],
+                }
+            }
+        }
+
+        extern fn can_become_main(this: &Object, _sel: Sel) -> BOOL {
+            unsafe {
+                match *this.get_ivar::<i8>("can_become_main") {
+                    0 => NO,
+                    1 => YES,
+                    _ => msg_send![super(this, Class::get("NSWindow").unwrap()), canBecomeMainWindow],
+                }
+            }
+        }
+
+        // Ctrl+Tab and Ctrl+Shift+Tab cycle through the native tabs, if any
+        extern fn perform_key_equivalent(this: &Object, _sel: Sel, nsevent: id) -> BOOL {
+            unsafe {
+                let key_code: u16 = msg_send![nsevent, keyCode];
+                let flags = nsevent.modifierFlags();
+                if key_code != KEY_CODE_TAB || !flags.contains(NSControlKeyMask) {
+                    return msg_send![super(this, Class::get("NSWindow").unwrap()), performKeyEquivalent:nsevent];
+                }
+                let previous = flags.contains(NSShiftKeyMask);
+                let nswindow = this as *const Object as id;
+                let tabs = get_tab_group_windows(nswindow);
+                if tabs.len() > 1 {
+                    // The key window may not have changed yet once
+                    // selectNextTab: returns, so don't ask NSApp.
+                    let window_number: NSInteger = msg_send![nswindow, windowNumber];
+                    let target = adjacent_tab(&tabs, WindowId(window_number as i64), previous);
+                    if previous {
+                        msg_send![nswindow, selectPreviousTab:nil];
+                    } else {
+                        msg_send![nswindow, selectNextTab:nil];
+                    }
+                    if let Some(target) = target {
+                        app::push_event(AppEvent::TabSelected(target));
+                    }
+                } else {
+                    let shortcut = if previous { Shortcut::PreviousTab } else { Shortcut::NextTab };
+                    app::push_event(AppEvent::Shortcut(shortcut));
+                }
+                YES
+            }
+        }
+
         extern fn awake_from_nib(this: &mut Object, _sel: Sel) {
             let event_queue: Vec<WindowEvent> = Vec::new();
             // FIXME: is that the best way to create a raw pointer?
             let event_queue_ptr = Box::into_raw(Box::new(event_queue));
+            let control_event_queue: Vec<ControlEvent> = Vec::new();
+            let control_event_queue_ptr = Box::into_raw(Box::new(control_event_queue));
             unsafe {
                 this.set_ivar("event_queue", event_queue_ptr as *mut c_void);
+                this.set_ivar("control_event_queue", control_event_queue_ptr as *mut c_void);
+                this.set_ivar::<i8>("can_become_key", -1);
+                this.set_ivar::<i8>("can_become_main", -1);
+                this.set_ivar::<CGFloat>("resize_grab_margin", DEFAULT_RESIZE_GRAB_MARGIN);
             }
         }
 
@@ -58,7 +200,11 @@ pub fn register() {
             class.add_method(sel!(toggleTabBar:), toggle_tabbar as extern fn(&Object, Sel, id));
             class.add_method(sel!(toggleToolbarShown:), toggle_toolbar as extern fn(&Object, Sel, id));
             class.add_method(sel!(eventLoopRised), event_loop_rised as extern fn(&Object, Sel));
+            class.add_method(sel!(canBecomeKeyWindow), can_become_key as extern fn(&Object, Sel) -> BOOL);
+            class.add_method(sel!(performKeyEquivalent:), perform_key_equivalent as extern fn(&Object, Sel, id) -> BOOL);
+            class.add_method(sel!(canBecomeMainWindow), can_become_main as extern fn(&Object, Sel) -> BOOL);
             class.add_method(sel!(awakeFromNib), awake_from_nib as extern fn(&mut Object, Sel));
+            class.add_method(sel!(sendEvent:), send_event as extern fn(&mut Object, Sel, id));
         }
 
         class.register();
@@ -201,6 +347,90 @@ pub fn register() {
             if on {1} else {0}
         }
 
+        extern fn observe_value(this: &Object, _sel: Sel, keypath: id, object: id, _change: id, _context: *mut c_void) {
+            unsafe {
+                // Changes when the toolbar slides in and out in fullscreen
+                if NSString::isEqualToString(keypath, "contentLayoutRect") {
+                    utils::get_event_queue(this).push(WindowEvent::GeometryDidChange);
+                }
+                if NSString::isEqualToString(keypath, "effectiveAppearance") {
+                    let appearance: id = msg_send![object, effectiveAppearance];
+                    let name: id = msg_send![appearance, name];
+                    // Covers NSAppearanceNameDarkAqua and NSAppearanceNameVibrantDark
+                    let appearance = if utils::nsstring_to_string(name).contains("Dark") {
+                        Appearance::Dark
+                    } else {
+                        Appearance::Light
+                    };
+                    let window_number: NSInteger = msg_send![object, windowNumber];
+                    app::push_event(AppEvent::WindowAppearanceChanged(WindowId(window_number as i64), appearance));
+                }
+            }
+        }
+
+        // The default implementation only knows how to drag file URLs
+        extern fn should_drag_document(_this: &Object, _sel: Sel, nswindow: id, _event: id, _from: NSPoint, pasteboard: id) -> BOOL {
+            unsafe {
+                let url: id = msg_send![nswindow, representedURL];
+                if url == nil {
+                    return NO;
+                }
+                let objects: id = msg_send![class("NSArray"), arrayWithObject:url];
+                msg_send![pasteboard, clearContents];
+                msg_send![pasteboard, writeObjects:objects];
+                let window_number: NSInteger = msg_send![nswindow, windowNumber];
+                app::push_event(AppEvent::ProxyIconDragged(WindowId(window_number as i64)));
+            }
+            YES
+        }
+
+        extern fn should_close(this: &Object, _sel: Sel, nswindow: id) -> BOOL {
+            utils::get_event_queue(this).push(WindowEvent::CloseRequested);
+            let blocked: BOOL = unsafe { *(&*nswindow).get_ivar("close_blocked") };
+            if blocked == YES {NO} else {YES}
+        }
+
+        extern fn active_space_did_change(this: &Object, _sel: Sel, _notification: id) {
+            utils::get_event_queue(this).push(WindowEvent::DidChangeSpace);
+        }
+
+        extern fn did_change_screen(_this: &Object, _sel: Sel, notification: id) {
+            unsafe {
+                let nswindow: id = msg_send![notification, object];
+                let nsscreen: id = msg_send![nswindow, screen];
+                if nsscreen == nil {
+                    return;
+                }
+                let rate = Screen::new(nsscreen).refresh_rate();
+                let previous: f32 = *(&*nswindow).get_ivar("refresh_rate");
+                if rate != previous {
+                    (*nswindow).set_ivar("refresh_rate", rate);
+                    let window_number: NSInteger = msg_send![nswindow, windowNumber];
+                    app::push_event(AppEvent::RefreshRateChanged(WindowId(window_number as i64), rate));
+                }
+            }
+        }
+
+        // Also reached when the window is picked from the Window menu
+        extern fn did_become_key(_this: &Object, _sel: Sel, notification: id) {
+            unsafe {
+                let nswindow: id = msg_send![notification, object];
+                let window_number: NSInteger = msg_send![nswindow, windowNumber];
+                app::push_event(AppEvent::WindowDidBecomeKey(WindowId(window_number as i64)));
+                view::report_modifiers(nswindow);
+                view::sync_secure_event_input();
+            }
+        }
+
+        extern fn will_use_fullscreen_presentation_options(_this: &Object, _sel: Sel, nswindow: id, proposed: NSUInteger) -> NSUInteger {
+            let autohide: BOOL = unsafe { *(&*nswindow).get_ivar("fullscreen_toolbar_autohide") };
+            if autohide == YES {
+                proposed | PRESENTATION_AUTO_HIDE_TOOLBAR
+            } else {
+                proposed & !PRESENTATION_AUTO_HIDE_TOOLBAR
+            }
+        }
+
         extern fn submit_user_input(this: &Object, _sel: Sel, item: id) {
             let text = unsafe {
                 let text: id = msg_send![item, stringValue];
@@ -219,6 +449,10 @@ pub fn register() {
             class.add_method(sel!(windowDidEnterFullScreen:), record_notification as extern fn(&Object, Sel, id));
             class.add_method(sel!(windowDidExitFullScreen:), record_notification as extern fn(&Object, Sel, id));
             class.add_method(sel!(windowWillClose:), record_notification as extern fn(&Object, Sel, id));
+            class.add_method(sel!(windowDidBecomeKey:), did_become_key as extern fn(&Object, Sel, id));
+            class.add_method(sel!(windowDidChangeScreen:), did_change_screen as extern fn(&Object, Sel, id));
+            class.add_method(sel!(activeSpaceDidChange:), active_space_did_change as extern fn(&Object, Sel, id));
+            class.add_method(sel!(windowShouldClose:), should_close as extern fn(&Object, Sel, id) -> BOOL);
 
             class.add_method(sel!(shellStop:), record_command as extern fn(&Object, Sel, id));
             class.add_method(sel!(shellReload:), record_command as extern fn(&Object, Sel, id));
@@ -247,6 +481,13 @@ pub fn register() {
             class.add_method(sel!(getStateForAction:), get_state_for_action as extern fn(&Object, Sel, Sel) -> NSInteger);
 
             class.add_method(sel!(shellSubmitUserInput:), submit_user_input as extern fn(&Object, Sel, id));
+
+            class.add_method(sel!(observeValueForKeyPath:ofObject:change:context:),
+                             observe_value as extern fn(&Object, Sel, id, id, id, *mut c_void));
+            class.add_method(sel!(window:willUseFullScreenPresentationOptions:),
+                             will_use_fullscreen_presentation_options as extern fn(&Object, Sel, id, NSUInteger) -> NSUInteger);
+            class.add_method(sel!(window:shouldDragDocumentWithEvent:from:withPasteboard:),
+                             should_drag_document as extern fn(&Object, Sel, id, id, NSPoint, id) -> BOOL);
         }
 
         class.register();
@@ -254,13 +495,196 @@ pub fn register() {
 }
 
 
+pub fn style_to_mask(style: WindowStyle) -> NSUInteger {
+    let mut mask = 0; // NSWindowStyleMaskBorderless
+    if style.contains(STYLE_TITLED) { mask |= 1 << 0; }
+    if style.contains(STYLE_CLOSABLE) { mask |= 1 << 1; }
+    if style.contains(STYLE_MINIATURIZABLE) { mask |= 1 << 2; }
+    if style.contains(STYLE_RESIZABLE) { mask |= 1 << 3; }
+    if style.contains(STYLE_FULL_SIZE_CONTENT) { mask |= 1 << 15; }
+    mask
+}
+
+// Edge and corner drags of frameless windows, see set_resize_affordance.
+// Returns true if the event started, continued or ended an edge drag, and
+// must not reach the window content.
+fn handle_resize_affordance(nswindow: &mut Object, nsevent: id) -> bool {
+    unsafe {
+        let enabled: BOOL = *nswindow.get_ivar("resize_affordance");
+        if enabled == NO {
+            return false;
+        }
+        let drag_ptr: *mut c_void = *nswindow.get_ivar("resize_drag");
+        match nsevent.eventType() {
+            NSLeftMouseDown if drag_ptr.is_null() => {
+                let margin: CGFloat = *nswindow.get_ivar("resize_grab_margin");
+                let frame: NSRect = msg_send![nswindow, frame];
+                let location = nsevent.locationInWindow();
+                let mut edges = 0;
+                if location.x < margin { edges |= EDGE_LEFT; }
+                if location.x > frame.size.width - margin { edges |= EDGE_RIGHT; }
+                if location.y < margin { edges |= EDGE_BOTTOM; }
+                if location.y > frame.size.height - margin { edges |= EDGE_TOP; }
+                if edges == 0 {
+                    return false;
+                }
+                let drag = ResizeDrag {
+                    edges: edges,
+                    start_frame: frame,
+                    start_mouse: msg_send![class("NSEvent"), mouseLocation],
+                };
+                nswindow.set_ivar("resize_drag", Box::into_raw(Box::new(drag)) as *mut c_void);
+                push_live_resize_event(nswindow, ViewEvent::LiveResizeStarted);
+                true
+            }
+            NSLeftMouseDragged if !drag_ptr.is_null() => {
+                let drag = &*(drag_ptr as *mut ResizeDrag);
+                let mouse: NSPoint = msg_send![class("NSEvent"), mouseLocation];
+                let min_size: NSSize = msg_send![nswindow, minSize];
+                let dx = mouse.x - drag.start_mouse.x;
+                let dy = mouse.y - drag.start_mouse.y;
+                let start = drag.start_frame;
+                let mut frame = start;
+                if drag.edges & EDGE_RIGHT != 0 {
+                    frame.size.width = (start.size.width + dx).max(min_size.width);
+                }
+                if drag.edges & EDGE_LEFT != 0 {
+                    frame.size.width = (start.size.width - dx).max(min_size.width);
+                    frame.origin.x = start.origin.x + start.size.width - frame.size.width;
+                }
+                if drag.edges & EDGE_TOP != 0 {
+                    frame.size.height = (start.size.height + dy).max(min_size.height);
+                }
+                if drag.edges & EDGE_BOTTOM != 0 {
+                    frame.size.height = (start.size.height - dy).max(min_size.height);
+                    frame.origin.y = start.origin.y + start.size.height - frame.size.height;
+                }
+                // The view reports GeometryDidChange
+                msg_send![nswindow, setFrame:frame display:YES];
+                true
+            }
+            NSLeftMouseUp if !drag_ptr.is_null() => {
+                drop(Box::from_raw(drag_ptr as *mut ResizeDrag));
+                nswindow.set_ivar("resize_drag", ptr::null_mut::<c_void>());
+                push_live_resize_event(nswindow, ViewEvent::LiveResizeEnded);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+// Like the NSServoView does for system live resizes
+fn push_live_resize_event(nswindow: &Object, event: ViewEvent) {
+    let nswindow = nswindow as *const Object as id;
+    if let Some(nsview) = utils::get_view_by_id(nswindow, "shellViewServo") {
+        utils::get_event_queue(unsafe { &*nsview }).push(event);
+    }
+}
+
+pub fn get_tab_group_windows(nswindow: id) -> Vec<WindowId> {
+    unsafe {
+        let window_number: NSInteger = msg_send![nswindow, windowNumber];
+        let this_window = WindowId(window_number as i64);
+        // tabGroup is 10.13+, tabbedWindows is there since 10.12
+        let has_tab_group: BOOL = msg_send![nswindow, respondsToSelector:sel!(tabGroup)];
+        let windows: id = if has_tab_group == YES {
+            let group: id = msg_send![nswindow, tabGroup];
+            msg_send![group, windows]
+        } else {
+            msg_send![nswindow, tabbedWindows]
+        };
+        if windows == nil {
+            return vec![this_window];
+        }
+        let count: NSInteger = msg_send![windows, count];
+        let mut ids = Vec::new();
+        for i in 0..count {
+            let nswindow: id = msg_send![windows, objectAtIndex:i];
+            let window_number: NSInteger = msg_send![nswindow, windowNumber];
+            ids.push(WindowId(window_number as i64));
+        }
+        if ids.is_empty() {
+            ids.push(this_window);
+        }
+        ids
+    }
+}
+
+// The tab selectNextTab: / selectPreviousTab: goes to, wrapping around.
+// `tabs` in tab bar order.
+fn adjacent_tab(tabs: &[WindowId], current: WindowId, previous: bool) -> Option<WindowId> {
+    let index = match tabs.iter().position(|&tab| tab == current) {
+        Some(index) => index,
+        None => return None,
+    };
+    let target = if previous {
+        (index + tabs.len() - 1) % tabs.len()
+    } else {
+        (index + 1) % tabs.len()
+    };
+    Some(tabs[target])
+}
+
+pub fn mask_to_style(mask: NSUInteger) -> WindowStyle {
+    let mut style = WindowStyle::empty();
+    if mask & (1 << 0) != 0 { style.insert(STYLE_TITLED); }
+    if mask & (1 << 1) != 0 { style.insert(STYLE_CLOSABLE); }
+    if mask & (1 << 2) != 0 { style.insert(STYLE_MINIATURIZABLE); }
+    if mask & (1 << 3) != 0 { style.insert(STYLE_RESIZABLE); }
+    if mask & (1 << 15) != 0 { style.insert(STYLE_FULL_SIZE_CONTENT); }
+    style
+}
+
+// The mask of `style`, keeping the bits of `current` WindowStyle doesn't
+// model (unified title and toolbar, fullscreen…)
+pub fn merge_style_mask(current: NSUInteger, style: WindowStyle) -> NSUInteger {
+    let preserved = current & !style_to_mask(WindowStyle::all());
+    style_to_mask(style) | preserved
+}
+
+// Override the nib geometry and style with whatever has been specified.
+pub fn apply_options(nswindow: id, opts: &WindowOptions) {
+    unsafe {
+        if opts.style.is_some() || opts.resizable.is_some() {
+            let current: NSUInteger = msg_send![nswindow, styleMask];
+            // Like Window::set_style, keep what WindowStyle doesn't cover
+            let mut mask = match opts.style {
+                Some(style) => merge_style_mask(current, style),
+                None => current,
+            };
+            match opts.resizable {
+                Some(true) => mask |= 1 << 3,
+                Some(false) => mask &= !(1 << 3),
+                None => {}
+            }
+            msg_send![nswindow, setStyleMask:mask];
+        }
+        if let Some((width, height)) = opts.size {
+            msg_send![nswindow, setContentSize:NSSize::new(width as f64, height as f64)];
+        }
+        if let Some((x, y)) = opts.position {
+            let screen: id = msg_send![class("NSScreen"), mainScreen];
+            let frame: NSRect = msg_send![screen, frame];
+            let point = NSPoint::new(x as f64, frame.size.height - y as f64);
+            msg_send![nswindow, setFrameTopLeftPoint:point];
+        }
+        if let Some(ref title) = opts.title {
+            let title = NSString::alloc(nil).init_str(title);
+            msg_send![nswindow, setTitle:title];
+        }
+    }
+}
+
 pub struct Window {
     nswindow: id,
     nspopover: id,
+    // Handed to the views, see AppOptions::clock
+    clock: Rc<Clock>,
 }
 
 impl Window {
-    pub fn new(nswindow: id, nspopover: id) -> Window {
+    pub fn new(nswindow: id, nspopover: id, clock: Rc<Clock>) -> Window {
 
         unsafe {
             // FIXME: release and set delegate to nil
@@ -270,21 +694,48 @@ impl Window {
 
             msg_send![nswindow, setDelegate:delegate];
 
+            let rate = Window::screen_refresh_rate(nswindow);
+            (*nswindow).set_ivar("refresh_rate", rate);
+
+            // effectiveAppearance is 10.14+
+            let observable: BOOL = msg_send![nswindow, respondsToSelector:sel!(effectiveAppearance)];
+            if observable == YES {
+                let keypath = NSString::alloc(nil).init_str("effectiveAppearance");
+                msg_send![nswindow, addObserver:delegate forKeyPath:keypath options:1 context:nil]; // NSKeyValueObservingOptionNew
+            }
+
+            let workspace: id = msg_send![class("NSWorkspace"), sharedWorkspace];
+            let center: id = msg_send![workspace, notificationCenter];
+            let name = NSString::alloc(nil).init_str("NSWorkspaceActiveSpaceDidChangeNotification");
+            msg_send![center, addObserver:delegate
+                                 selector:sel!(activeSpaceDidChange:)
+                                     name:name
+                                   object:nil];
+
+            let keypath = NSString::alloc(nil).init_str("contentLayoutRect");
+            msg_send![nswindow, addObserver:delegate forKeyPath:keypath options:1 context:nil];
+
             msg_send![nspopover, setBehavior:1]; // NSPopoverBehaviorTransient
 
             nswindow.setTitleVisibility_(NSWindowTitleVisibility::NSWindowTitleHidden);
             nswindow.setAcceptsMouseMovedEvents_(YES);
 
+            // Listed in the Window menu under its title, even if the title
+            // itself is hidden.
+            msg_send![nswindow, setExcludedFromWindowsMenu:NO];
+
             // Necessary to prevent the log view to wrap text
-            let textview = utils::get_view_by_id(nswindow, "shellViewLogsTextView").unwrap();
-            let text_container: id = msg_send![textview, textContainer];
-            msg_send![text_container, setWidthTracksTextView:NO];
-            msg_send![text_container, setContainerSize:NSSize::new(f64::MAX, f64::MAX)];
+            if let Some(textview) = utils::get_view_by_id(nswindow, "shellViewLogsTextView") {
+                let text_container: id = msg_send![textview, textContainer];
+                msg_send![text_container, setWidthTracksTextView:NO];
+                msg_send![text_container, setContainerSize:NSSize::new(f64::MAX, f64::MAX)];
+            }
         }
 
         Window {
             nswindow: nswindow,
             nspopover: nspopover,
+            clock: clock,
         }
     }
 
@@ -367,7 +818,10 @@ impl Window {
         }
 
         // Show logs if necessary
-        let logs = utils::get_view_by_id(self.nswindow, "shellViewLogs").unwrap();
+        let logs = match utils::get_view_by_id(self.nswindow, "shellViewLogs") {
+            Some(logs) => logs,
+            None => return,
+        };
         let visible = get_state().window_states[0].logs_visible;
         let hidden = if visible {NO} else {YES};
         unsafe {msg_send![logs, setHidden:hidden]};
@@ -382,12 +836,16 @@ impl Window {
         }
     }
 
-    pub fn create_view(&self) -> Result<View, &'static str> {
+    pub fn create_view(&self) -> Result<View, ShellError> {
         // FIXME: We should dynamically create a NSServoView,
         // and adds the constraints, instead on relying on IB's instance.
-        utils::get_view_by_id(self.nswindow, "shellViewServo")
-            .map(|nsview| View::new(nsview))
-            .ok_or("Can't find NSServoView")
+        match utils::get_view_by_id(self.nswindow, "shellViewServo") {
+            Some(nsview) if utils::id_is_instance_of(nsview, "NSServoView") => Ok(View::new(nsview, self.clock.clone())),
+            _ => {
+                let content_view: id = unsafe { msg_send![self.nswindow, contentView] };
+                Err(ShellError::ServoViewNotFound(utils::describe_view_hierarchy(content_view)))
+            }
+        }
     }
 
     pub fn toggle_sidebar(&self) {
@@ -395,7 +853,10 @@ impl Window {
         // we need to have access to "animator()" which, afaiu, comes only
         // from a NSSplitViewController. We want to be able to use this:
         // https://developer.apple.com/reference/appkit/nssplitviewcontroller/1388905-togglesidebar
-        let sidebar = utils::get_view_by_id(self.nswindow, "shellViewSidebar").unwrap();
+        let sidebar = match utils::get_view_by_id(self.nswindow, "shellViewSidebar") {
+            Some(sidebar) => sidebar,
+            None => return,
+        };
         unsafe {
             let hidden: BOOL = msg_send![sidebar, isHidden];
             if hidden == YES {
@@ -407,8 +868,11 @@ impl Window {
     }
 
     pub fn append_logs(&self, logs: &Vec<ShellLog>) {
+        let textview = match utils::get_view_by_id(self.nswindow, "shellViewLogsTextView") {
+            Some(textview) => textview,
+            None => return,
+        };
         unsafe {
-            let textview = utils::get_view_by_id(self.nswindow, "shellViewLogsTextView").unwrap();
             let textstorage: id = msg_send![textview, textStorage];
             // FIXME: figure out how to add colors
             for l in logs {
@@ -420,24 +884,49 @@ impl Window {
         }
     }
 
+    /// Ordered by priority, see `priority::Priority`.
     pub fn get_events(&self) -> Vec<WindowEvent> {
         let nsobject = unsafe { &*self.nswindow};
-        utils::get_event_queue(nsobject).drain(..).collect()
+        priority::sort_by_priority(utils::get_event_queue(nsobject).drain(..).collect())
+    }
+
+    pub fn id(&self) -> WindowId {
+        let window_number: NSInteger = unsafe {
+            msg_send![self.nswindow, windowNumber]
+        };
+        WindowId(window_number as i64)
+    }
+
+    /// Windows of the native tab group this window belongs to, in tab order.
+    pub fn tab_group_windows(&self) -> Vec<WindowId> {
+        get_tab_group_windows(self.nswindow)
     }
 
     pub fn set_url(&self, url: &str) {
-        // FIXME: can't get NSWindow::representedURL to work
-        let item = self.get_toolbar_item("urlbar").unwrap();
+        if let Some(field) = self.get_urlbar_textfield() {
+            unsafe {
+                let string = NSString::alloc(nil).init_str(url);
+                msg_send![field, setStringValue:string];
+            }
+        }
+        self.set_represented_url(url);
+    }
+
+    // Gives the title bar a proxy icon that can be dragged to other apps
+    // (only visible if the title is visible).
+    fn set_represented_url(&self, url: &str) {
         unsafe {
-            let view = msg_send![item, view];
-            let field = utils::get_view_by_id(view, "shellToolbarViewUrlbarTextfield").unwrap();
             let string = NSString::alloc(nil).init_str(url);
-            msg_send![field, setStringValue:string];
+            let nsurl: id = msg_send![class("NSURL"), URLWithString:string];
+            msg_send![self.nswindow, setRepresentedURL:nsurl];
         }
     }
 
     pub fn set_status(&self, status: Option<String>) {
-        let textfield = utils::get_view_by_id(self.nswindow, "shellStatusLabel").unwrap();
+        let textfield = match utils::get_view_by_id(self.nswindow, "shellStatusLabel") {
+            Some(textfield) => textfield,
+            None => return,
+        };
         match status {
             Some(status) => {
                 unsafe {
@@ -453,17 +942,17 @@ impl Window {
     }
 
     pub fn focus_urlbar(&self) {
-        let item = self.get_toolbar_item("urlbar").unwrap();
-        unsafe {
-            let view = msg_send![item, view];
-            let field = utils::get_view_by_id(view, "shellToolbarViewUrlbarTextfield").unwrap();
-            msg_send![field, becomeFirstResponder];
+        if let Some(field) = self.get_urlbar_textfield() {
+            unsafe { msg_send![field, becomeFirstResponder] };
         }
     }
 
     pub fn show_options(&self) {
+        let item = match self.get_toolbar_item("options") {
+            Some(item) => item,
+            None => return,
+        };
         unsafe {
-            let item = self.get_toolbar_item("options").unwrap();
             let button: id = msg_send![item, view];
             let bounds = NSView::bounds(button);
             msg_send![self.nspopover, showRelativeToRect:bounds ofView:button preferredEdge:3];
@@ -471,25 +960,324 @@ impl Window {
     }
 
     fn get_toolbar_item(&self, identifier: &str) -> Option<id> {
+        utils::get_toolbar_item(self.nswindow, identifier)
+    }
+
+    fn get_urlbar_textfield(&self) -> Option<id> {
+        self.get_toolbar_item("urlbar").and_then(|item| {
+            let view: id = unsafe { msg_send![item, view] };
+            utils::get_view_by_id(view, "shellToolbarViewUrlbarTextfield")
+        })
+    }
+
+    pub fn get_controls(&self) -> Controls {
+        Controls::new(self.nswindow)
+    }
+
+    pub fn set_title(&self, title: &str) {
         unsafe {
-            let toolbar: id = msg_send![self.nswindow, toolbar];
-            let items: id = msg_send![toolbar, items];
-            let count: NSInteger = msg_send![items, count];
-            for i in 0..count {
-                let item: id = msg_send![items, objectAtIndex:i];
-                let item_identifier: id = msg_send![item, itemIdentifier];
-                if NSString::isEqualToString(item_identifier, identifier) {
-                    return Some(item);
+            let title = NSString::alloc(nil).init_str(title);
+            msg_send![self.nswindow, setTitle:title]
+        }
+    }
+
+    /// Title shown in the native tab, independently of the window title.
+    /// An empty string goes back to showing the window title.
+    pub fn set_tab_title(&self, title: &str) {
+        unsafe {
+            let tab = self.get_tab();
+            if tab == nil {
+                return;
+            }
+            let title = if title.is_empty() {
+                nil
+            } else {
+                NSString::alloc(nil).init_str(title)
+            };
+            msg_send![tab, setTitle:title];
+        }
+    }
+
+    /// View shown next to the tab title (favicon, close button…). Pass nil to remove it.
+    pub fn set_tab_accessory_view(&self, view: id) {
+        unsafe {
+            let tab = self.get_tab();
+            if tab != nil {
+                msg_send![tab, setAccessoryView:view];
+            }
+        }
+    }
+
+    /// Overrides the system preference for this window. `Preferred` opens
+    /// it as a tab of the frontmost window, `Disallowed` as a separate
+    /// window. Must be set before the window is ordered in.
+    pub fn set_tabbing_mode(&self, mode: TabbingMode) {
+        // NSWindowTabbingMode values
+        let mode: NSInteger = match mode {
+            TabbingMode::Automatic => 0,
+            TabbingMode::Preferred => 1,
+            TabbingMode::Disallowed => 2,
+        };
+        unsafe {
+            // 10.12+
+            let supported: BOOL = msg_send![self.nswindow, respondsToSelector:sel!(setTabbingMode:)];
+            if supported == YES {
+                msg_send![self.nswindow, setTabbingMode:mode];
+            }
+        }
+    }
+
+    // NSWindowTab is 10.13+
+    fn get_tab(&self) -> id {
+        unsafe {
+            let has_tab: BOOL = msg_send![self.nswindow, respondsToSelector:sel!(tab)];
+            if has_tab == YES {
+                msg_send![self.nswindow, tab]
+            } else {
+                nil
+            }
+        }
+    }
+
+    /// Of the screen the window is on (or the main screen if offscreen), in Hz.
+    pub fn current_refresh_rate(&self) -> f32 {
+        Window::screen_refresh_rate(self.nswindow)
+    }
+
+    fn screen_refresh_rate(nswindow: id) -> f32 {
+        let nsscreen: id = unsafe { msg_send![nswindow, screen] };
+        if nsscreen == nil {
+            Screen::main().refresh_rate()
+        } else {
+            Screen::new(nsscreen).refresh_rate()
+        }
+    }
+
+    pub fn style(&self) -> WindowStyle {
+        let mask: NSUInteger = unsafe { msg_send![self.nswindow, styleMask] };
+        mask_to_style(mask)
+    }
+
+    /// Apply all the style bits at once. Other bits of the window (unified
+    /// title and toolbar, fullscreen state…) are kept. Without
+    /// STYLE_FULL_SIZE_CONTENT, the toolbar isn't laid out over the content
+    /// anymore.
+    pub fn set_style(&self, style: WindowStyle) {
+        unsafe {
+            let current: NSUInteger = msg_send![self.nswindow, styleMask];
+            let mask = merge_style_mask(current, style);
+            if mask != current {
+                msg_send![self.nswindow, setStyleMask:mask];
+                utils::get_event_queue(&*self.nswindow).push(WindowEvent::GeometryDidChange);
+            }
+        }
+    }
+
+    /// Interactive resizes snap the content size to multiples of (dw, dh),
+    /// in points. (1, 1) disables snapping. Sizes set programmatically
+    /// aren't affected.
+    pub fn set_resize_increments(&self, dw: u32, dh: u32) {
+        unsafe {
+            let increments = NSSize::new(dw.max(1) as f64, dh.max(1) as f64);
+            msg_send![self.nswindow, setContentResizeIncrements:increments];
+        }
+    }
+
+    pub fn set_resizable(&self, resizable: bool) {
+        self.set_style_bit(STYLE_RESIZABLE, resizable);
+    }
+
+    pub fn set_closable(&self, closable: bool) {
+        self.set_style_bit(STYLE_CLOSABLE, closable);
+    }
+
+    pub fn set_miniaturizable(&self, miniaturizable: bool) {
+        self.set_style_bit(STYLE_MINIATURIZABLE, miniaturizable);
+    }
+
+    fn set_style_bit(&self, bit: WindowStyle, enabled: bool) {
+        let mut style = self.style();
+        if enabled {
+            style.insert(bit);
+        } else {
+            style.remove(bit);
+        }
+        self.set_style(style);
+    }
+
+    /// Like clicking the close button: asks windowShouldClose: (CloseRequested),
+    /// then animates and closes the window, unless the close is blocked.
+    pub fn perform_close(&self) {
+        unsafe {
+            msg_send![self.nswindow, performClose:nil];
+        }
+    }
+
+    /// Close the window unconditionally. No CloseRequested.
+    pub fn close(&self) {
+        unsafe {
+            msg_send![self.nswindow, close];
+        }
+    }
+
+    /// Freeze what's on screen (all windows) until `enable_screen_updates`,
+    /// to show several changes (resize, move, new content) at once. Calls
+    /// must be balanced. Anything left disabled is re-enabled at the end of
+    /// the current event loop iteration (and by AppKit after a second).
+    pub fn disable_screen_updates(&self) {
+        app::disable_screen_updates();
+    }
+
+    pub fn enable_screen_updates(&self) {
+        app::enable_screen_updates();
+    }
+
+    /// Let the user resize the window by dragging its edges and corners.
+    /// Meant for windows without STYLE_RESIZABLE, like frameless ones.
+    /// The view reports the usual LiveResizeStarted, GeometryDidChange and
+    /// LiveResizeEnded events.
+    pub fn set_resize_affordance(&self, enabled: bool) {
+        unsafe {
+            (*self.nswindow).set_ivar("resize_affordance", if enabled {YES} else {NO});
+        }
+    }
+
+    /// How close to an edge (in points) a drag starts a resize. 5 by default.
+    pub fn set_resize_grab_margin(&self, margin: f32) {
+        unsafe {
+            (*self.nswindow).set_ivar::<CGFloat>("resize_grab_margin", margin as CGFloat);
+        }
+    }
+
+    /// While blocked, CloseRequested is still sent but the window stays open
+    /// (for example to prompt about unsaved state, then call `close`).
+    pub fn set_close_blocked(&self, blocked: bool) {
+        unsafe {
+            (*self.nswindow).set_ivar("close_blocked", if blocked {YES} else {NO});
+        }
+    }
+
+    /// While in native fullscreen, letterbox the content to this aspect ratio
+    /// (width, height). The bars are painted with the window background color
+    /// (see `set_background_color`). No effect when windowed. None to remove.
+    pub fn set_fullscreen_aspect_ratio(&self, ratio: Option<(u32, u32)>) {
+        let (width, height) = ratio.unwrap_or((0, 0));
+        unsafe {
+            (*self.nswindow).set_ivar("fullscreen_aspect_width", width);
+            (*self.nswindow).set_ivar("fullscreen_aspect_height", height);
+        }
+        utils::get_event_queue(unsafe { &*self.nswindow }).push(WindowEvent::GeometryDidChange);
+    }
+
+    /// Components between 0 and 1.
+    pub fn set_background_color(&self, r: f32, g: f32, b: f32, a: f32) {
+        unsafe {
+            let color: id = msg_send![class("NSColor"), colorWithSRGBRed:r as CGFloat
+                                                                   green:g as CGFloat
+                                                                    blue:b as CGFloat
+                                                                   alpha:a as CGFloat];
+            msg_send![self.nswindow, setBackgroundColor:color];
+        }
+    }
+
+    /// Show the window on all Spaces. Moving a window to a given Space
+    /// isn't supported: there's no public API for it.
+    pub fn set_sticky(&self, sticky: bool) {
+        // NSWindowCollectionBehaviorCanJoinAllSpaces
+        self.set_collection_behavior(1 << 0, sticky);
+    }
+
+    pub fn is_on_active_space(&self) -> bool {
+        let on_active_space: BOOL = unsafe { msg_send![self.nswindow, isOnActiveSpace] };
+        on_active_space == YES
+    }
+
+    /// Whether the window can go native fullscreen. When not allowed, the
+    /// green button zooms instead, and `toggle_fullscreen` does nothing.
+    /// For dialog-like windows.
+    pub fn set_fullscreen_allowed(&self, allowed: bool) {
+        self.set_collection_behavior(COLLECTION_BEHAVIOR_FULLSCREEN_NONE, !allowed);
+    }
+
+    pub fn is_fullscreen_allowed(&self) -> bool {
+        let behavior: NSUInteger = unsafe { msg_send![self.nswindow, collectionBehavior] };
+        behavior & COLLECTION_BEHAVIOR_FULLSCREEN_NONE == 0
+    }
+
+    /// Enter or exit native fullscreen. DidEnterFullScreen or DidExitFullScreen
+    /// follows. No-op if fullscreen isn't allowed.
+    pub fn toggle_fullscreen(&self) {
+        if !self.is_fullscreen_allowed() {
+            return;
+        }
+        unsafe {
+            msg_send![self.nswindow, toggleFullScreen:nil];
+        }
+    }
+
+    fn set_collection_behavior(&self, bit: NSUInteger, enabled: bool) {
+        unsafe {
+            let mut behavior: NSUInteger = msg_send![self.nswindow, collectionBehavior];
+            if enabled {
+                behavior |= bit;
+            } else {
+                behavior &= !bit;
+            }
+            msg_send![self.nswindow, setCollectionBehavior:behavior];
+        }
+    }
+
+    /// Hide the close, minimize and zoom buttons. The style mask is left
+    /// untouched: the title bar can still be dragged, and Cmd+W,
+    /// `perform_close` and the Window menu keep working.
+    pub fn set_traffic_lights_hidden(&self, hidden: bool) {
+        unsafe {
+            // NSWindowCloseButton, NSWindowMiniaturizeButton, NSWindowZoomButton
+            for kind in 0..3 {
+                let button: id = msg_send![self.nswindow, standardWindowButton:kind as NSUInteger];
+                if button != nil {
+                    msg_send![button, setHidden:if hidden {YES} else {NO}];
                 }
             }
-            None
         }
     }
 
-    pub fn set_title(&self, title: &str) {
+    /// Borderless windows can't become key (no keyboard input) by default.
+    pub fn set_can_become_key(&self, can_become_key: bool) {
         unsafe {
-            let title = NSString::alloc(nil).init_str(title);
-            msg_send![self.nswindow, setTitle:title]
+            (*self.nswindow).set_ivar::<i8>("can_become_key", if can_become_key {1} else {0});
+        }
+    }
+
+    /// Borderless windows can't become main by default.
+    pub fn set_can_become_main(&self, can_become_main: bool) {
+        unsafe {
+            (*self.nswindow).set_ivar::<i8>("can_become_main", if can_become_main {1} else {0});
+        }
+    }
+
+    /// In native fullscreen, hide the toolbar until the pointer reaches the
+    /// top of the screen (like Safari). The content area follows the toolbar
+    /// as it's revealed (GeometryDidChange). Takes effect the next time
+    /// the window enters fullscreen. Off by default.
+    pub fn set_fullscreen_toolbar_autohide(&self, autohide: bool) {
+        unsafe {
+            (*self.nswindow).set_ivar("fullscreen_toolbar_autohide", if autohide {YES} else {NO});
+        }
+    }
+
+    /// DocumentWindow for browser windows, None for transient popups.
+    pub fn set_animation_behavior(&self, behavior: AnimationBehavior) {
+        // NSWindowAnimationBehavior values
+        let behavior: NSInteger = match behavior {
+            AnimationBehavior::Default => 0,
+            AnimationBehavior::None => 2,
+            AnimationBehavior::DocumentWindow => 3,
+            AnimationBehavior::UtilityWindow => 4,
+            AnimationBehavior::AlertPanel => 5,
+        };
+        unsafe {
+            msg_send![self.nswindow, setAnimationBehavior:behavior];
         }
     }
 
@@ -560,36 +1348,58 @@ impl Window {
             (NSAppearanceNameVibrantLight, YES, 0)
         }};
 
-        let item = self.get_toolbar_item("options").unwrap();
-        let topview = unsafe {
-            let view: id = msg_send![item, view];
-            let view: id = msg_send![view, superview];
-            msg_send![view, superview]
-        };
-        utils::get_view(topview, &|view| {
-            if utils::id_is_instance_of(view, "NSButton") {
-                unsafe {msg_send![view, setBordered:bordered]};
-            }
-            if utils::id_is_instance_of(view, "NSSegmentedControl") {
-                unsafe {msg_send![view, setSegmentStyle:segment_style]};
-            }
-            if utils::id_is_instance_of(view, "NSTextField") {
-                unsafe {
-                    let layer: id = msg_send![view, layer];
-                    msg_send![layer, setCornerRadius:3.0];
-                    let alpha = if dark {0.1} else {0.0};
-                    let color: id = msg_send![Class::get("NSColor").unwrap(), colorWithRed:1.0 green:1.0 blue:1.0 alpha:alpha];
-                    let color: id = msg_send![color, CGColor];
-                    msg_send![layer, setBackgroundColor:color];
+        if let Some(item) = self.get_toolbar_item("options") {
+            let topview = unsafe {
+                let view: id = msg_send![item, view];
+                let view: id = msg_send![view, superview];
+                msg_send![view, superview]
+            };
+            utils::get_view(topview, &|view| {
+                if utils::id_is_instance_of(view, "NSButton") {
+                    unsafe {msg_send![view, setBordered:bordered]};
                 }
-            }
-            false
-        });
+                if utils::id_is_instance_of(view, "NSSegmentedControl") {
+                    unsafe {msg_send![view, setSegmentStyle:segment_style]};
+                }
+                if utils::id_is_instance_of(view, "NSTextField") {
+                    unsafe {
+                        let layer: id = msg_send![view, layer];
+                        msg_send![layer, setCornerRadius:3.0];
+                        let alpha = if dark {0.1} else {0.0};
+                        let color: id = msg_send![Class::get("NSColor").unwrap(), colorWithRed:1.0 green:1.0 blue:1.0 alpha:alpha];
+                        let color: id = msg_send![color, CGColor];
+                        msg_send![layer, setBackgroundColor:color];
+                    }
+                }
+                false
+            });
+        }
         unsafe {
-            let appearance: id = msg_send![class("NSAppearance"), appearanceNamed:appearance];
+            let mut appearance: id = msg_send![class("NSAppearance"), appearanceNamed:appearance];
+            let disabled: BOOL = *(&*self.nswindow).get_ivar("vibrancy_disabled");
+            let vibrancy = disabled == NO && !App::reduce_transparency();
+            if !vibrancy {
+                // NSAppearanceNameDarkAqua is 10.14+. Before that, there's no
+                // solid dark appearance, so we stick to the vibrant one.
+                let name = if dark {"NSAppearanceNameDarkAqua"} else {"NSAppearanceNameAqua"};
+                let name = NSString::alloc(nil).init_str(name);
+                let solid: id = msg_send![class("NSAppearance"), appearanceNamed:name];
+                if solid != nil {
+                    appearance = solid;
+                }
+            }
             msg_send![self.nswindow, setAppearance:appearance];
         }
     }
+
+    /// Use the translucent (vibrant) appearances. Ignored, and a solid
+    /// appearance is used instead, if the user asked to reduce transparency.
+    pub fn set_vibrancy(&self, enabled: bool) {
+        unsafe {
+            (*self.nswindow).set_ivar("vibrancy_disabled", if enabled {NO} else {YES});
+        }
+        self.update_theme();
+    }
 }
 
 pub struct EventLoopRiser {
@@ -621,3 +1431,83 @@ impl EventLoopRiser {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{adjacent_tab, mask_to_style, merge_style_mask, style_to_mask};
+    use window::{WindowId, WindowStyle, STYLE_TITLED, STYLE_CLOSABLE, STYLE_MINIATURIZABLE, STYLE_RESIZABLE, STYLE_FULL_SIZE_CONTENT};
+
+    #[test]
+    fn standard_window_mask() {
+        let style = STYLE_TITLED | STYLE_CLOSABLE | STYLE_RESIZABLE | STYLE_MINIATURIZABLE;
+        // NSWindowStyleMaskTitled | Closable | Miniaturizable | Resizable
+        assert_eq!(style_to_mask(style), 0b1111);
+        assert_eq!(style_to_mask(STYLE_TITLED | STYLE_CLOSABLE), 0b11);
+    }
+
+    #[test]
+    fn frameless_window_mask() {
+        // NSWindowStyleMaskBorderless
+        assert_eq!(style_to_mask(WindowStyle::empty()), 0);
+        // Resizable, but no titlebar bits
+        assert_eq!(style_to_mask(STYLE_RESIZABLE), 1 << 3);
+        assert_eq!(style_to_mask(STYLE_RESIZABLE | STYLE_FULL_SIZE_CONTENT), (1 << 3) | (1 << 15));
+        assert_eq!(mask_to_style(0), WindowStyle::empty());
+    }
+
+    #[test]
+    fn full_size_content_bit_follows_the_style() {
+        let current = style_to_mask(STYLE_TITLED | STYLE_FULL_SIZE_CONTENT);
+        let mask = merge_style_mask(current, STYLE_TITLED | STYLE_CLOSABLE | STYLE_FULL_SIZE_CONTENT);
+        assert_eq!(mask, 0b11 | (1 << 15));
+        assert!(mask_to_style(mask).contains(STYLE_FULL_SIZE_CONTENT));
+        let mask = merge_style_mask(current, STYLE_TITLED | STYLE_CLOSABLE);
+        assert_eq!(mask, 0b11);
+        // Modeled bits of the current mask go away
+        assert_eq!(merge_style_mask(0b1111, STYLE_TITLED), 0b1);
+    }
+
+    #[test]
+    fn unmodeled_bits_are_preserved() {
+        // Like Window.nib: titled, closable, miniaturizable, resizable,
+        // unified title and toolbar, full size content view
+        let nib = 0b1111 | (1 << 12) | (1 << 15);
+        let mut style = mask_to_style(nib);
+        style.remove(STYLE_CLOSABLE);
+        let mask = merge_style_mask(nib, style);
+        assert_eq!(mask, nib & !(1 << 1));
+        // Fullscreen
+        let mask = merge_style_mask(nib | (1 << 14), WindowStyle::empty());
+        assert_eq!(mask, (1 << 12) | (1 << 14));
+    }
+
+    #[test]
+    fn mask_round_trip() {
+        let styles = [
+            WindowStyle::empty(),
+            STYLE_TITLED,
+            STYLE_RESIZABLE,
+            STYLE_TITLED | STYLE_CLOSABLE | STYLE_MINIATURIZABLE | STYLE_RESIZABLE,
+            STYLE_TITLED | STYLE_CLOSABLE | STYLE_MINIATURIZABLE | STYLE_RESIZABLE | STYLE_FULL_SIZE_CONTENT,
+            WindowStyle::all(),
+        ];
+        for style in styles.iter() {
+            assert_eq!(mask_to_style(style_to_mask(*style)), *style);
+        }
+    }
+
+    #[test]
+    fn adjacent_tab_wraps_around() {
+        let tabs = [WindowId(1), WindowId(2), WindowId(3)];
+        assert_eq!(adjacent_tab(&tabs, WindowId(1), false), Some(WindowId(2)));
+        assert_eq!(adjacent_tab(&tabs, WindowId(3), false), Some(WindowId(1)));
+        assert_eq!(adjacent_tab(&tabs, WindowId(2), true), Some(WindowId(1)));
+        assert_eq!(adjacent_tab(&tabs, WindowId(1), true), Some(WindowId(3)));
+    }
+
+    #[test]
+    fn adjacent_tab_of_unknown_window() {
+        let tabs = [WindowId(1), WindowId(2)];
+        assert_eq!(adjacent_tab(&tabs, WindowId(4), false), None);
+    }
+}
@@ -0,0 +1,110 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+/// Events are returned by `get_events` in priority order (High first).
+/// Within a priority, events are returned in the order they were queued.
+/// Events are never moved past a barrier: everything queued before a
+/// barrier is returned before it, everything queued after, after it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    High,
+    Medium,
+    Low,
+}
+
+pub trait Prioritized {
+    fn priority(&self) -> Priority;
+
+    /// For events that depend on the ones queued before them. For example,
+    /// a click needs the mouse moves that preceded it to be processed first.
+    fn is_barrier(&self) -> bool {
+        false
+    }
+}
+
+pub fn sort_by_priority<T: Prioritized>(events: Vec<T>) -> Vec<T> {
+    let mut sorted = Vec::with_capacity(events.len());
+    let mut segment: Vec<T> = Vec::new();
+    for event in events {
+        if event.is_barrier() {
+            // sort_by_key is stable
+            segment.sort_by_key(|e| e.priority());
+            sorted.extend(segment.drain(..));
+            sorted.push(event);
+        } else {
+            segment.push(event);
+        }
+    }
+    segment.sort_by_key(|e| e.priority());
+    sorted.extend(segment);
+    sorted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sort_by_priority, Priority, Prioritized};
+
+    // A name, a priority, and whether it's a barrier
+    #[derive(Debug, PartialEq)]
+    struct Event(&'static str, Priority, bool);
+
+    impl Prioritized for Event {
+        fn priority(&self) -> Priority {
+            self.1
+        }
+        fn is_barrier(&self) -> bool {
+            self.2
+        }
+    }
+
+    fn names(events: Vec<Event>) -> Vec<&'static str> {
+        events.into_iter().map(|e| e.0).collect()
+    }
+
+    #[test]
+    fn sorted_by_priority_and_stable() {
+        let events = vec![
+            Event("low1", Priority::Low, false),
+            Event("medium1", Priority::Medium, false),
+            Event("high1", Priority::High, false),
+            Event("low2", Priority::Low, false),
+            Event("high2", Priority::High, false),
+            Event("medium2", Priority::Medium, false),
+        ];
+        assert_eq!(names(sort_by_priority(events)),
+                   vec!["high1", "high2", "medium1", "medium2", "low1", "low2"]);
+    }
+
+    #[test]
+    fn barriers_are_not_crossed() {
+        let events = vec![
+            Event("low1", Priority::Low, false),
+            Event("medium1", Priority::Medium, false),
+            Event("barrier1", Priority::Low, true),
+            Event("low2", Priority::Low, false),
+            Event("high1", Priority::High, false),
+            Event("barrier2", Priority::Low, true),
+            Event("barrier3", Priority::Low, true),
+            Event("medium2", Priority::Medium, false),
+            Event("high2", Priority::High, false),
+        ];
+        assert_eq!(names(sort_by_priority(events)),
+                   vec!["medium1", "low1", "barrier1", "high1", "low2", "barrier2", "barrier3", "high2", "medium2"]);
+    }
+
+    #[test]
+    fn empty_input() {
+        assert_eq!(sort_by_priority(Vec::<Event>::new()), vec![]);
+    }
+
+    #[test]
+    fn same_priority_keeps_the_order() {
+        let events = vec![
+            Event("a", Priority::Medium, false),
+            Event("b", Priority::Medium, false),
+            Event("c", Priority::Medium, false),
+        ];
+        assert_eq!(names(sort_by_priority(events)), vec!["a", "b", "c"]);
+    }
+}
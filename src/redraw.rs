@@ -0,0 +1,158 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use clock::duration_to_secs;
+use std::time::Duration;
+
+/// What to do with a redraw request.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RedrawAction {
+    /// Deliver a RedrawRequested now.
+    Deliver,
+    /// Nothing yet. Check again (see `RedrawThrottle::flush`) in that many
+    /// seconds, once the interval is over.
+    ScheduleIn(f64),
+    /// Nothing. A redraw is already waiting for the interval to end.
+    Wait,
+}
+
+/// Coalesces the redraw requests made within a minimum interval into a
+/// single one, delivered at the end of the interval.
+pub struct RedrawThrottle {
+    // In seconds. 0.0: no throttling
+    interval: f64,
+    last_redraw: Option<f64>,
+    pending: bool,
+}
+
+impl RedrawThrottle {
+    pub fn new() -> RedrawThrottle {
+        RedrawThrottle {
+            interval: 0.0,
+            last_redraw: None,
+            pending: false,
+        }
+    }
+
+    /// A redraw is requested at `now` (see `Clock::now`).
+    pub fn request(&mut self, now: f64) -> RedrawAction {
+        let elapsed = self.last_redraw.map(|last| now - last);
+        match elapsed {
+            Some(elapsed) if self.interval > 0.0 && elapsed < self.interval => {
+                if self.pending {
+                    RedrawAction::Wait
+                } else {
+                    self.pending = true;
+                    RedrawAction::ScheduleIn(self.interval - elapsed)
+                }
+            }
+            _ => {
+                self.pending = false;
+                self.last_redraw = Some(now);
+                RedrawAction::Deliver
+            }
+        }
+    }
+
+    /// A zero duration disables throttling, and delivers what was waiting
+    /// for the interval to end.
+    pub fn set_interval(&mut self, interval: Duration, now: f64) -> RedrawAction {
+        self.interval = duration_to_secs(interval);
+        if self.interval == 0.0 && self.pending {
+            self.request(now)
+        } else {
+            RedrawAction::Wait
+        }
+    }
+
+    /// Deliver the coalesced redraw if its interval is over.
+    pub fn flush(&mut self, now: f64) -> RedrawAction {
+        if !self.pending {
+            return RedrawAction::Wait;
+        }
+        let last = self.last_redraw.unwrap_or(now);
+        if now - last >= self.interval {
+            self.request(now)
+        } else {
+            RedrawAction::Wait
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RedrawAction, RedrawThrottle};
+    use clock::{Clock, MockClock};
+    use std::time::Duration;
+
+    #[test]
+    fn requests_within_the_interval_are_coalesced() {
+        // Powers of two, so that the boundary is exact
+        let step = Duration::new(0, 62_500_000);
+        let clock = MockClock::new();
+        let mut throttle = RedrawThrottle::new();
+        throttle.set_interval(Duration::from_secs(1), clock.now());
+        assert_eq!(throttle.request(clock.now()), RedrawAction::Deliver);
+
+        let mut scheduled = Vec::new();
+        for _ in 0..8 {
+            clock.advance(step);
+            match throttle.request(clock.now()) {
+                RedrawAction::Deliver => panic!("delivered within the interval"),
+                RedrawAction::ScheduleIn(delay) => scheduled.push(delay),
+                RedrawAction::Wait => {}
+            }
+            assert_eq!(throttle.flush(clock.now()), RedrawAction::Wait);
+        }
+        assert_eq!(scheduled, vec![0.9375]);
+
+        // Just before the boundary, then at the boundary
+        clock.advance(Duration::new(0, 437_500_000));
+        assert_eq!(throttle.flush(clock.now()), RedrawAction::Wait);
+        clock.advance(step);
+        assert_eq!(throttle.flush(clock.now()), RedrawAction::Deliver);
+
+        // Only once
+        assert_eq!(throttle.flush(clock.now()), RedrawAction::Wait);
+        clock.advance(Duration::from_secs(2));
+        assert_eq!(throttle.flush(clock.now()), RedrawAction::Wait);
+    }
+
+    #[test]
+    fn zero_interval_delivers_immediately() {
+        let clock = MockClock::new();
+        let mut throttle = RedrawThrottle::new();
+        for _ in 0..3 {
+            assert_eq!(throttle.request(clock.now()), RedrawAction::Deliver);
+        }
+        clock.advance(Duration::from_millis(1));
+        assert_eq!(throttle.request(clock.now()), RedrawAction::Deliver);
+        assert_eq!(throttle.flush(clock.now()), RedrawAction::Wait);
+    }
+
+    #[test]
+    fn switching_to_zero_flushes_the_pending_redraw() {
+        let clock = MockClock::new();
+        let mut throttle = RedrawThrottle::new();
+        throttle.set_interval(Duration::from_millis(100), clock.now());
+        assert_eq!(throttle.request(clock.now()), RedrawAction::Deliver);
+        clock.advance(Duration::from_millis(10));
+        match throttle.request(clock.now()) {
+            RedrawAction::ScheduleIn(delay) => assert!((delay - 0.09).abs() < 1e-9),
+            action => panic!("unexpected {:?}", action),
+        }
+
+        assert_eq!(throttle.set_interval(Duration::from_millis(0), clock.now()), RedrawAction::Deliver);
+        assert_eq!(throttle.flush(clock.now()), RedrawAction::Wait);
+        assert_eq!(throttle.request(clock.now()), RedrawAction::Deliver);
+    }
+
+    #[test]
+    fn switching_to_zero_without_pending_redraw_does_nothing() {
+        let clock = MockClock::new();
+        let mut throttle = RedrawThrottle::new();
+        throttle.set_interval(Duration::from_millis(100), clock.now());
+        assert_eq!(throttle.set_interval(Duration::from_millis(0), clock.now()), RedrawAction::Wait);
+    }
+}
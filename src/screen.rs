@@ -0,0 +1,28 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+pub use platform::Screen;
+
+/// Identifies a display (wraps its CGDirectDisplayID).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ScreenId(pub u32);
+
+/// What Servo needs to answer the pointer, hover and resolution media
+/// queries. See `App::device_metrics`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DeviceMetrics {
+    /// Most accurate pointing device available.
+    pub pointer: Pointer,
+    /// Can any of the pointing devices hover over elements.
+    pub hover: bool,
+    /// Physical size of the main screen, in millimeters. (0, 0) if unknown.
+    pub screen_mm: (f32, f32),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pointer {
+    None,
+    Coarse,
+    Fine,
+}
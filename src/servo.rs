@@ -10,7 +10,7 @@ use self::servo::servo_config::prefs::{PrefValue, PREFS};
 use self::servo::servo_config::resource_files::set_resources_path;
 use self::servo::compositing::windowing::{MouseWindowEvent, WindowMethods, WindowEvent, WindowNavigateMsg};
 use self::servo::compositing::compositor_thread::{self, CompositorProxy, CompositorReceiver};
-use self::servo::msg::constellation_msg::{self, HistoryEntries, Key};
+use self::servo::msg::constellation_msg::{self, HistoryEntries, Key, KeyModifiers, KeyState};
 use self::servo::servo_geometry::DeviceIndependentPixel;
 use self::servo::euclid::{Point2D, Size2D};
 use self::servo::euclid::scale_factor::ScaleFactor;
@@ -30,6 +30,7 @@ use view;
 use view::DrawableGeometry;
 use platform::EventLoopRiser;
 
+use std::ascii::AsciiExt;
 use std::sync::mpsc;
 use std::rc::Rc;
 use std::cell::{Cell, RefCell};
@@ -159,9 +160,10 @@ impl Servo {
 
     fn substract_margins(&self, x: i32, y: i32) -> (i32, i32) {
         let geometry = self.callbacks.geometry.get();
+        let (scale_x, scale_y) = geometry.render_scale();
         let (top, _, _, left) = geometry.margins;
-        let top = top as f32 * geometry.hidpi_factor;
-        let left = left as f32 * geometry.hidpi_factor;
+        let top = top as f32 * scale_y;
+        let left = left as f32 * scale_x;
         let x = x - left as i32;
         let y = y - top as i32;
         (x, y)
@@ -180,11 +182,27 @@ impl Servo {
             view::TouchPhase::Started => TouchEventType::Down,
             view::TouchPhase::Moved => TouchEventType::Move,
             view::TouchPhase::Ended => TouchEventType::Up,
+            view::TouchPhase::Cancelled => TouchEventType::Cancel,
         };
         let event = WindowEvent::Scroll(scroll_location, TypedPoint2D::new(x, y), phase);
         self.events_for_servo.borrow_mut().push(event);
     }
 
+    pub fn perform_text_input(&self, text: &str) {
+        for ch in text.chars().filter(|ch| !ch.is_control()) {
+            // FIXME: Servo wants a key for each character, so characters
+            // without one (composed by an input method…) are dropped
+            let key = match char_to_key(ch) {
+                Some(key) => key,
+                None => continue,
+            };
+            for state in &[KeyState::Pressed, KeyState::Released] {
+                let event = WindowEvent::KeyEvent(Some(ch), key, *state, KeyModifiers::empty());
+                self.events_for_servo.borrow_mut().push(event);
+            }
+        }
+    }
+
     pub fn update_geometry(&self, geometry: DrawableGeometry) {
         self.callbacks.geometry.set(geometry);
         let event = WindowEvent::Resize(self.callbacks.framebuffer_size());
@@ -316,20 +334,19 @@ impl WindowMethods for ServoCallbacks {
     }
 
     fn framebuffer_size(&self) -> TypedSize2D<u32, DevicePixel> {
-        let scale_factor = self.geometry.get().hidpi_factor as u32;
-        let (width, height) = self.geometry.get().view_size;
-        TypedSize2D::new(scale_factor * width, scale_factor * height)
+        let (width, height) = self.geometry.get().render_size;
+        TypedSize2D::new(width, height)
     }
 
     fn window_rect(&self) -> TypedRect<u32, DevicePixel> {
-        let scale_factor = self.geometry.get().hidpi_factor as u32;
+        let (scale_x, scale_y) = self.geometry.get().render_scale();
         let mut size = self.framebuffer_size();
 
         let (top, right, bottom, left) = self.geometry.get().margins;
-        let top = top * scale_factor;
-        let right = right * scale_factor;
-        let bottom = bottom * scale_factor;
-        let left = left * scale_factor;
+        let top = (top as f32 * scale_y) as u32;
+        let right = (right as f32 * scale_x) as u32;
+        let bottom = (bottom as f32 * scale_y) as u32;
+        let left = (left as f32 * scale_x) as u32;
 
         size.height = size.height - top - bottom;
         size.width = size.width - left - right;
@@ -430,3 +447,59 @@ impl CompositorProxy for ShellCompositorProxy {
         } as Box<CompositorProxy + Send>
     }
 }
+
+// Same mapping as Servo's glutin port
+fn char_to_key(ch: char) -> Option<Key> {
+    let key = match ch.to_ascii_uppercase() {
+        ' ' => Key::Space,
+        '\'' | '"' => Key::Apostrophe,
+        ',' | '<' => Key::Comma,
+        '-' | '_' => Key::Minus,
+        '.' | '>' => Key::Period,
+        '/' | '?' => Key::Slash,
+        '0' | ')' => Key::Num0,
+        '1' | '!' => Key::Num1,
+        '2' | '@' => Key::Num2,
+        '3' | '#' => Key::Num3,
+        '4' | '$' => Key::Num4,
+        '5' | '%' => Key::Num5,
+        '6' | '^' => Key::Num6,
+        '7' | '&' => Key::Num7,
+        '8' | '*' => Key::Num8,
+        '9' | '(' => Key::Num9,
+        ';' | ':' => Key::Semicolon,
+        '=' | '+' => Key::Equal,
+        'A' => Key::A,
+        'B' => Key::B,
+        'C' => Key::C,
+        'D' => Key::D,
+        'E' => Key::E,
+        'F' => Key::F,
+        'G' => Key::G,
+        'H' => Key::H,
+        'I' => Key::I,
+        'J' => Key::J,
+        'K' => Key::K,
+        'L' => Key::L,
+        'M' => Key::M,
+        'N' => Key::N,
+        'O' => Key::O,
+        'P' => Key::P,
+        'Q' => Key::Q,
+        'R' => Key::R,
+        'S' => Key::S,
+        'T' => Key::T,
+        'U' => Key::U,
+        'V' => Key::V,
+        'W' => Key::W,
+        'X' => Key::X,
+        'Y' => Key::Y,
+        'Z' => Key::Z,
+        '[' | '{' => Key::LeftBracket,
+        '\\' | '|' => Key::Backslash,
+        ']' | '}' => Key::RightBracket,
+        '`' | '~' => Key::GraveAccent,
+        _ => return None,
+    };
+    Some(key)
+}
@@ -2,12 +2,17 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use app::AboutOptions;
 use view;
 
 pub struct AppState {
     pub current_window_index: Option<u32>,
     pub window_states: Vec<WindowState>,
     pub dark_theme: bool,
+    /// Most recent first
+    pub recent_urls: Vec<String>,
+    /// None: the About menu item is left to the embedder
+    pub about_options: Option<AboutOptions>,
 }
 
 pub struct WindowState {
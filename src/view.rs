@@ -3,15 +3,66 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 pub use platform::View;
+use priority::{Priority, Prioritized};
 
 #[derive(Debug, Copy, Clone)]
 pub struct DrawableGeometry {
     pub view_size: (u32, u32),
+    /// Size of the framebuffer. view_size * hidpi_factor, unless the
+    /// view has a fixed render size. See `View::set_render_size`.
+    pub render_size: (u32, u32),
     pub margins: (u32, u32, u32, u32),
     pub position: (i32, i32),
     pub hidpi_factor: f32,
 }
 
+impl DrawableGeometry {
+    /// Framebuffer pixels per point, for each axis.
+    pub fn render_scale(&self) -> (f32, f32) {
+        let (width, height) = self.view_size;
+        let (render_width, render_height) = self.render_size;
+        if width == 0 || height == 0 {
+            return (self.hidpi_factor, self.hidpi_factor)
+        }
+        (render_width as f32 / width as f32, render_height as f32 / height as f32)
+    }
+
+    /// Which part of the view a point is in. Same coordinates as
+    /// `ViewEvent::MouseMoved`: device pixels from the top left of the view.
+    /// Corners belong to the top and bottom margins.
+    pub fn classify_point(&self, x: i32, y: i32) -> Region {
+        let (scale_x, scale_y) = self.render_scale();
+        let (top, right, bottom, left) = self.margins;
+        let width = (self.view_size.0 as f32 * scale_x) as i32;
+        let height = (self.view_size.1 as f32 * scale_y) as i32;
+        if x < 0 || y < 0 || x >= width || y >= height {
+            return Region::Outside;
+        }
+        if y < (top as f32 * scale_y) as i32 {
+            Region::TopMargin
+        } else if y >= height - (bottom as f32 * scale_y) as i32 {
+            Region::BottomMargin
+        } else if x < (left as f32 * scale_x) as i32 {
+            Region::LeftMargin
+        } else if x >= width - (right as f32 * scale_x) as i32 {
+            Region::RightMargin
+        } else {
+            Region::Content
+        }
+    }
+}
+
+/// See `DrawableGeometry::classify_point`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Region {
+    Content,
+    TopMargin,
+    RightMargin,
+    BottomMargin,
+    LeftMargin,
+    Outside,
+}
+
 /// View events
 
 #[derive(Debug, Clone)]
@@ -20,6 +71,81 @@ pub enum ViewEvent {
     MouseWheel(MouseScrollDelta, TouchPhase),
     MouseInput(ElementState, MouseButton),
     MouseMoved(i32, i32),
+    LiveResizeStarted,
+    LiveResizeEnded,
+    EditAction(EditAction, ActionSource),
+    /// Sent periodically while a selection is dragged close to the edges of
+    /// the view, see `View::set_autoscroll_enabled`. How far the pointer is
+    /// into the edge zone, in device pixels. Positive means right/down.
+    Autoscroll(f32, f32),
+    /// Quick Look / Look Up gesture, at that location (device pixels).
+    QuickLook((i32, i32)),
+    /// Sent once, if `View::start_display_link` had to fall back to a timer.
+    DisplayLinkFallback,
+    /// Time to paint, see `View::request_redraw`.
+    RedrawRequested,
+    /// Lock keys state changed.
+    LockKeysChanged(LockKeys),
+    /// Current state of the modifier keys. Sent when the app is activated
+    /// and when the window becomes key, as modifiers may have been released
+    /// while another app had the focus.
+    ModifiersChanged(Modifiers),
+    /// Follows a MouseInput press: 1 for a single click, 2 for a double
+    /// click… Counted by the system, or by `click::ClickCounter` as a
+    /// fallback, see `View::set_double_click_interval`.
+    ClickCount(MouseButton, u32),
+    /// Text typed, or committed by the input method.
+    TextInput(String),
+    /// Text being composed by the input method, not committed yet. None
+    /// once the composition is over (see `TextInput` for its result).
+    MarkedTextChanged(Option<String>),
+}
+
+impl Prioritized for ViewEvent {
+    fn priority(&self) -> Priority {
+        match *self {
+            ViewEvent::MouseInput(..) |
+            ViewEvent::EditAction(..) |
+            ViewEvent::ModifiersChanged(..) |
+            ViewEvent::ClickCount(..) |
+            ViewEvent::TextInput(..) |
+            ViewEvent::MarkedTextChanged(..) => Priority::High,
+            ViewEvent::GeometryDidChange |
+            ViewEvent::LiveResizeStarted |
+            ViewEvent::LiveResizeEnded |
+            ViewEvent::MouseWheel(..) |
+            ViewEvent::QuickLook(..) |
+            ViewEvent::DisplayLinkFallback |
+            ViewEvent::LockKeysChanged(..) => Priority::Medium,
+            ViewEvent::MouseMoved(..) |
+            ViewEvent::Autoscroll(..) |
+            ViewEvent::RedrawRequested => Priority::Low,
+        }
+    }
+
+    // Clicks happen where the last MouseMoved was
+    fn is_barrier(&self) -> bool {
+        match *self {
+            ViewEvent::MouseInput(..) => true,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditAction {
+    Cut,
+    Copy,
+    Paste,
+    SelectAll,
+}
+
+/// Where an action was triggered from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActionSource {
+    Menu,
+    Keyboard,
+    ContextMenu,
 }
 
 #[derive(Debug, Clone)]
@@ -27,6 +153,10 @@ pub enum TouchPhase {
     Started,
     Moved,
     Ended,
+    /// For MouseWheel: the momentum of the previous scroll was interrupted
+    /// (a new scroll started, or the fingers touched the trackpad again).
+    /// The inertial scroll should stop.
+    Cancelled,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -47,3 +177,168 @@ pub enum MouseScrollDelta {
 	LineDelta(f32, f32),
 	PixelDelta(f32, f32)
 }
+
+/// What dropping the dragged content would do. Drives the cursor shown,
+/// and what is reported to the drag source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DragOperation {
+    None,
+    Copy,
+    Move,
+    Link,
+}
+
+/// Kind of the focused text field. Decides which input methods are
+/// available while typing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextInputType {
+    Normal,
+    Password,
+    Email,
+    Number,
+    Search,
+    None,
+}
+
+/// Rubber-band effect when scrolling past the edges, see
+/// `View::set_scroll_elasticity`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Elasticity {
+    /// Let the system decide (NSScrollElasticityAutomatic).
+    Automatic,
+    /// Always bounce. Same as Automatic without an enclosing scroll view.
+    Allowed,
+    /// Never bounce. Without an enclosing scroll view, inertial scrolling
+    /// is disabled on that axis altogether.
+    None,
+}
+
+/// Only `caps` is reliable. Mac keyboards have no Num Lock or Scroll Lock,
+/// so `num` and `scroll` are best-effort and usually false.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LockKeys {
+    pub caps: bool,
+    pub num: bool,
+    pub scroll: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub ctrl: bool,
+    pub alt: bool,
+    pub cmd: bool,
+}
+
+/// Pixels read back from the view, see `View::capture`. RGBA, 8 bits per
+/// channel, rows from top to bottom.
+#[derive(Clone, Debug)]
+pub struct ImageData {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DrawableGeometry, Region};
+    use std::i32;
+
+    // Margins are top, right, bottom, left
+    fn geometry(render_size: (u32, u32), hidpi_factor: f32, margins: (u32, u32, u32, u32)) -> DrawableGeometry {
+        DrawableGeometry {
+            view_size: (100, 50),
+            render_size: render_size,
+            margins: margins,
+            position: (0, 0),
+            hidpi_factor: hidpi_factor,
+        }
+    }
+
+    #[test]
+    fn margin_boundaries_at_1x() {
+        let g = geometry((100, 50), 1.0, (10, 5, 8, 3));
+        assert_eq!(g.classify_point(50, 9), Region::TopMargin);
+        assert_eq!(g.classify_point(50, 10), Region::Content);
+        assert_eq!(g.classify_point(50, 41), Region::Content);
+        assert_eq!(g.classify_point(50, 42), Region::BottomMargin);
+        assert_eq!(g.classify_point(50, 49), Region::BottomMargin);
+        assert_eq!(g.classify_point(2, 20), Region::LeftMargin);
+        assert_eq!(g.classify_point(3, 20), Region::Content);
+        assert_eq!(g.classify_point(94, 20), Region::Content);
+        assert_eq!(g.classify_point(95, 20), Region::RightMargin);
+        assert_eq!(g.classify_point(99, 20), Region::RightMargin);
+    }
+
+    #[test]
+    fn margin_boundaries_at_2x() {
+        let g = geometry((200, 100), 2.0, (10, 5, 8, 3));
+        assert_eq!(g.classify_point(100, 19), Region::TopMargin);
+        assert_eq!(g.classify_point(100, 20), Region::Content);
+        assert_eq!(g.classify_point(100, 83), Region::Content);
+        assert_eq!(g.classify_point(100, 84), Region::BottomMargin);
+        assert_eq!(g.classify_point(5, 50), Region::LeftMargin);
+        assert_eq!(g.classify_point(6, 50), Region::Content);
+        assert_eq!(g.classify_point(189, 50), Region::Content);
+        assert_eq!(g.classify_point(190, 50), Region::RightMargin);
+        assert_eq!(g.classify_point(199, 50), Region::RightMargin);
+        assert_eq!(g.classify_point(200, 50), Region::Outside);
+    }
+
+    #[test]
+    fn corners_belong_to_top_and_bottom_margins() {
+        let g = geometry((100, 50), 1.0, (10, 5, 8, 3));
+        assert_eq!(g.classify_point(0, 0), Region::TopMargin);
+        assert_eq!(g.classify_point(99, 0), Region::TopMargin);
+        assert_eq!(g.classify_point(2, 9), Region::TopMargin);
+        assert_eq!(g.classify_point(95, 9), Region::TopMargin);
+        assert_eq!(g.classify_point(0, 49), Region::BottomMargin);
+        assert_eq!(g.classify_point(99, 49), Region::BottomMargin);
+        assert_eq!(g.classify_point(2, 42), Region::BottomMargin);
+        assert_eq!(g.classify_point(95, 42), Region::BottomMargin);
+    }
+
+    #[test]
+    fn zero_margins() {
+        let g = geometry((100, 50), 1.0, (0, 0, 0, 0));
+        assert_eq!(g.classify_point(0, 0), Region::Content);
+        assert_eq!(g.classify_point(99, 49), Region::Content);
+        assert_eq!(g.classify_point(100, 0), Region::Outside);
+        assert_eq!(g.classify_point(0, 50), Region::Outside);
+
+        let g = geometry((200, 100), 2.0, (0, 0, 0, 0));
+        assert_eq!(g.classify_point(0, 0), Region::Content);
+        assert_eq!(g.classify_point(199, 99), Region::Content);
+        assert_eq!(g.classify_point(200, 99), Region::Outside);
+        assert_eq!(g.classify_point(199, 100), Region::Outside);
+    }
+
+    #[test]
+    fn fixed_render_size() {
+        // Not view_size * hidpi_factor: 1.5 framebuffer pixels per point
+        let g = geometry((150, 75), 2.0, (10, 5, 8, 3));
+        assert_eq!(g.classify_point(75, 14), Region::TopMargin);
+        assert_eq!(g.classify_point(75, 15), Region::Content);
+        assert_eq!(g.classify_point(75, 62), Region::Content);
+        assert_eq!(g.classify_point(75, 63), Region::BottomMargin);
+        assert_eq!(g.classify_point(3, 30), Region::LeftMargin);
+        assert_eq!(g.classify_point(4, 30), Region::Content);
+        assert_eq!(g.classify_point(142, 30), Region::Content);
+        assert_eq!(g.classify_point(143, 30), Region::RightMargin);
+        assert_eq!(g.classify_point(149, 74), Region::BottomMargin);
+        assert_eq!(g.classify_point(150, 30), Region::Outside);
+        assert_eq!(g.classify_point(75, 75), Region::Outside);
+    }
+
+    #[test]
+    fn out_of_range_points_are_outside() {
+        let g = geometry((100, 50), 1.0, (10, 5, 8, 3));
+        assert_eq!(g.classify_point(-1, 20), Region::Outside);
+        assert_eq!(g.classify_point(50, -1), Region::Outside);
+        assert_eq!(g.classify_point(-1, -1), Region::Outside);
+        assert_eq!(g.classify_point(100, 20), Region::Outside);
+        assert_eq!(g.classify_point(50, 50), Region::Outside);
+        assert_eq!(g.classify_point(i32::MIN, i32::MIN), Region::Outside);
+        assert_eq!(g.classify_point(i32::MAX, i32::MAX), Region::Outside);
+    }
+}
@@ -3,6 +3,11 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 pub use platform::Window;
+use priority::{Priority, Prioritized};
+
+/// Identifies a native window (wraps its window number).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct WindowId(pub i64);
 
 #[derive(Clone, Debug)]
 pub enum WindowEvent {
@@ -11,9 +16,29 @@ pub enum WindowEvent {
     DidEnterFullScreen,
     DidExitFullScreen,
     WillClose,
+    /// The close button, Cmd+W or `Window::perform_close`. Followed by
+    /// WillClose, unless the close is blocked (see `Window::set_close_blocked`).
+    CloseRequested,
+    /// The active Space changed. See `Window::is_on_active_space`.
+    DidChangeSpace,
     DoCommand(WindowCommand),
 }
 
+impl Prioritized for WindowEvent {
+    fn priority(&self) -> Priority {
+        match *self {
+            WindowEvent::WillClose |
+            WindowEvent::CloseRequested |
+            WindowEvent::DoCommand(..) => Priority::High,
+            WindowEvent::EventLoopRised |
+            WindowEvent::GeometryDidChange |
+            WindowEvent::DidEnterFullScreen |
+            WindowEvent::DidExitFullScreen |
+            WindowEvent::DidChangeSpace => Priority::Medium,
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum WindowCommand {
     Reload,
@@ -37,3 +62,48 @@ pub enum WindowCommand {
     ToggleOptionWebRenderStats,
     ToggleOptionTileBorders,
 }
+
+/// How the window animates when it's ordered in and out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnimationBehavior {
+    Default,
+    None,
+    DocumentWindow,
+    UtilityWindow,
+    AlertPanel,
+}
+
+/// Whether this window opens as a tab of an existing window.
+/// `Automatic` follows the "Prefer tabs" system preference.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TabbingMode {
+    Automatic,
+    Preferred,
+    Disallowed,
+}
+
+/// Initial geometry and style used by `App::create_window_with`.
+/// Fields set to `None` fall back to what Window.nib specifies.
+#[derive(Clone, Debug, Default)]
+pub struct WindowOptions {
+    /// Size of the content area, in points.
+    pub size: Option<(u32, u32)>,
+    /// Top-left corner of the window, in points, from the top-left of the main screen.
+    pub position: Option<(i32, i32)>,
+    pub title: Option<String>,
+    /// Overrides the STYLE_RESIZABLE bit of `style`.
+    pub resizable: Option<bool>,
+    /// Same as Window::set_style: the nib bits WindowStyle doesn't cover
+    /// (unified title and toolbar) are kept.
+    pub style: Option<WindowStyle>,
+}
+
+bitflags! {
+    pub flags WindowStyle: u32 {
+        const STYLE_TITLED            = 1 << 0,
+        const STYLE_CLOSABLE          = 1 << 1,
+        const STYLE_MINIATURIZABLE    = 1 << 2,
+        const STYLE_RESIZABLE         = 1 << 3,
+        const STYLE_FULL_SIZE_CONTENT = 1 << 4,
+    }
+}